@@ -0,0 +1,214 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use bevy::audio::Volume;
+
+// Plays every sound in the game
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AudioSettings::new())
+            .insert_resource(LoopPlayer::default())
+            .add_systems(Startup, load_sounds)
+            .add_systems(OnEnter(GameState::Ready), play_intro)
+            .add_systems(OnEnter(GameState::Dying), play_death)
+            .add_systems(Update, (
+                play_chomp,
+                update_loop_track,
+                apply_siren_pitch,
+                apply_volume_settings,
+            ).chain());
+    }
+}
+
+// Volumes from 0.0 to 1.0, music and SFX are both scaled by master
+#[derive(Resource)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+}
+
+impl AudioSettings {
+    pub fn new() -> Self {
+        AudioSettings {
+            master: 1.0,
+            music: 0.6,
+            sfx: 0.8,
+        }
+    }
+
+    pub fn music_volume(&self) -> Volume {
+        Volume::new(self.master * self.music)
+    }
+
+    pub fn sfx_volume(&self) -> Volume {
+        Volume::new(self.master * self.sfx)
+    }
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Resource)]
+pub struct SoundAssets {
+    intro: Handle<AudioSource>,
+    chomp: [Handle<AudioSource>; 2],
+    siren: Handle<AudioSource>,
+    frightened: Handle<AudioSource>,
+    eyes: Handle<AudioSource>,
+    death: Handle<AudioSource>,
+}
+
+// Background loops, only one plays at a time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopTrack {
+    Siren,
+    Frightened,
+    Eyes,
+}
+
+// The loop currently playing and the entity carrying its sink
+#[derive(Resource, Default)]
+pub struct LoopPlayer {
+    track: Option<LoopTrack>,
+    entity: Option<Entity>,
+}
+
+// Constants
+const SIREN_MAX_SPEEDUP: f32 = 0.6; // Siren plays this much faster with the last pellet
+
+pub fn load_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(SoundAssets {
+        intro: asset_server.load("sounds/intro.wav"),
+        chomp: [
+            asset_server.load("sounds/chomp_a.wav"),
+            asset_server.load("sounds/chomp_b.wav"),
+        ],
+        siren: asset_server.load("sounds/siren.wav"),
+        frightened: asset_server.load("sounds/frightened.wav"),
+        eyes: asset_server.load("sounds/eyes.wav"),
+        death: asset_server.load("sounds/death.wav"),
+    });
+}
+
+// Fire and forget a sound, the entity despawns when it finishes
+fn play_once(commands: &mut Commands, source: Handle<AudioSource>, volume: Volume) {
+    commands.spawn(AudioBundle {
+        source,
+        settings: PlaybackSettings::DESPAWN.with_volume(volume),
+    });
+}
+
+// The intro jingle plays on the first Ready of each game only
+pub fn play_intro(
+    mut commands: Commands,
+    stats: Res<GameStats>,
+    sounds: Res<SoundAssets>,
+    settings: Res<AudioSettings>,
+) {
+    if !stats.is_new_game() {
+        return;
+    }
+    play_once(&mut commands, sounds.intro.clone(), settings.music_volume());
+}
+
+pub fn play_death(
+    mut commands: Commands,
+    sounds: Res<SoundAssets>,
+    settings: Res<AudioSettings>,
+) {
+    play_once(&mut commands, sounds.death.clone(), settings.music_volume());
+}
+
+// Alternate between the two halves of the "waka" for each pellet
+pub fn play_chomp(
+    mut commands: Commands,
+    mut pellet_events: EventReader<PelletEatenEvent>,
+    mut chomp_toggle: Local<bool>,
+    sounds: Res<SoundAssets>,
+    settings: Res<AudioSettings>,
+) {
+    for _ in pellet_events.read() {
+        let index = *chomp_toggle as usize;
+        *chomp_toggle = !*chomp_toggle;
+        play_once(&mut commands, sounds.chomp[index].clone(), settings.sfx_volume());
+    }
+}
+
+// Eyes beat frightened, frightened beats the siren, silence outside of play
+pub fn update_loop_track(
+    mut commands: Commands,
+    mut player: ResMut<LoopPlayer>,
+    sounds: Res<SoundAssets>,
+    settings: Res<AudioSettings>,
+    state: Res<State<GameState>>,
+    ghost_query: Query<&Ghost>,
+) {
+    let wanted = if *state.get() != GameState::Playing {
+        None
+    } else if ghost_query.iter().any(|ghost| ghost.mode == GhostMode::Eaten) {
+        Some(LoopTrack::Eyes)
+    } else if ghost_query.iter().any(|ghost| ghost.mode == GhostMode::Frightened) {
+        Some(LoopTrack::Frightened)
+    } else {
+        Some(LoopTrack::Siren)
+    };
+
+    if wanted == player.track {
+        return;
+    }
+    if let Some(entity) = player.entity.take() {
+        commands.entity(entity).despawn();
+    }
+    player.track = wanted;
+    player.entity = wanted.map(|track| {
+        let source = match track {
+            LoopTrack::Siren => sounds.siren.clone(),
+            LoopTrack::Frightened => sounds.frightened.clone(),
+            LoopTrack::Eyes => sounds.eyes.clone(),
+        };
+        commands.spawn(AudioBundle {
+            source,
+            settings: PlaybackSettings::LOOP.with_volume(settings.music_volume()),
+        }).id()
+    });
+}
+
+// The siren rises in pitch as the maze empties
+pub fn apply_siren_pitch(
+    player: Res<LoopPlayer>,
+    stats: Res<GameStats>,
+    sink_query: Query<&AudioSink>,
+) {
+    if player.track != Some(LoopTrack::Siren) {
+        return;
+    }
+    let Some(sink) = player.entity.and_then(|entity| sink_query.get(entity).ok()) else {
+        return;
+    };
+    let total = stats.pellets_eaten + stats.pellets_remaining;
+    let progress = if total > 0 {
+        stats.pellets_eaten as f32 / total as f32
+    } else {
+        0.0
+    };
+    sink.set_speed(1.0 + progress * SIREN_MAX_SPEEDUP);
+}
+
+// Volume changes apply to the loop that is already playing
+pub fn apply_volume_settings(
+    player: Res<LoopPlayer>,
+    settings: Res<AudioSettings>,
+    sink_query: Query<&AudioSink>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    if let Some(sink) = player.entity.and_then(|entity| sink_query.get(entity).ok()) {
+        sink.set_volume(settings.music_volume().get());
+    }
+}
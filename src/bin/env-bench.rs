@@ -0,0 +1,40 @@
+// Run the headless environment flat out with random actions and report how fast it steps
+// Usage: cargo run --release --bin env-bench -- [steps] [seed]
+use pacman_bevy::prelude::*;
+use rand::Rng;
+use std::time::Instant;
+
+// Constants
+const DEFAULT_STEPS: usize = 20_000;
+const ACTION_HOLD: usize = 15; // Ticks each random action is held for, like a player would
+
+fn main() -> Result<(), String> {
+    let mut args = std::env::args().skip(1);
+    let steps: usize = args.next().and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_STEPS);
+    let seed: u64 = args.next().and_then(|arg| arg.parse().ok()).unwrap_or(0);
+
+    let mut env = PacmanEnv::new();
+    let mut rng = GameRng::new(seed);
+    env.reset(seed)?;
+
+    let mut episodes = 1;
+    let mut episode_reward = 0.0;
+    let mut action = PacManDirection::Stop;
+    let started = Instant::now();
+    for step in 0..steps {
+        if step % ACTION_HOLD == 0 {
+            action = PacManDirection::ALL[rng.gen_range(0..PacManDirection::ALL.len())];
+        }
+        let (_observation, reward, done) = env.step(action)?;
+        episode_reward += reward;
+        if done {
+            println!("Episode {} finished with reward {}", episodes, episode_reward);
+            episodes += 1;
+            episode_reward = 0.0;
+            env.reset(seed + episodes as u64)?;
+        }
+    }
+    let seconds = started.elapsed().as_secs_f64();
+    println!("{} steps in {:.2}s, {:.0} steps/s", steps, seconds, steps as f64 / seconds);
+    Ok(())
+}
@@ -0,0 +1,42 @@
+// Check maze files for structural problems before they ship
+// Usage: cargo run --bin maze-check -- [maze files...]
+// Exits non-zero if any maze fails to load or has a problem, so CI can run it
+use pacman_bevy::prelude::*;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut paths: Vec<String> = std::env::args().skip(1).collect();
+    if paths.is_empty() {
+        paths.push(DEFAULT_MAZE_PATH.to_string());
+    }
+
+    let mut failed = false;
+    for path in paths.iter() {
+        let maze = match Maze::read_map(path) {
+            Ok(maze) => maze,
+            Err(e) => {
+                eprintln!("{}: failed to read maze: {}", path, e);
+                failed = true;
+                continue;
+            }
+        };
+
+        let report = validate_maze(&maze);
+        let problems = report.problems();
+        if problems.is_empty() {
+            println!("{}: ok ({})", path, report.summary());
+            continue;
+        }
+        failed = true;
+        println!("{}: {} problem(s)", path, problems.len());
+        for problem in problems {
+            println!("  {}", problem);
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,55 @@
+// Generate mazes from a seed, or fuzz the node builder with a run of generated mazes
+// Usage: cargo run --bin maze-gen -- [seed] [output file]
+//        cargo run --bin maze-gen -- --fuzz [count] [first seed]
+// Fuzzing exits non-zero if any maze fails validation: a dead-end node, a node graph that
+// doesn't match the maze, or anything else maze-check would complain about
+use pacman_bevy::prelude::*;
+use std::process::ExitCode;
+
+// Constants
+const DEFAULT_FUZZ_COUNT: u64 = 1000;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("--fuzz") {
+        let count = args.get(1).and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_FUZZ_COUNT);
+        let first_seed = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(0);
+        return fuzz(first_seed, count);
+    }
+
+    let seed: u64 = args.first().and_then(|arg| arg.parse().ok()).unwrap_or(0);
+    let maze = generate_maze(&mut GameRng::new(seed));
+    match args.get(1) {
+        Some(path) => {
+            if let Err(e) = maze.write_map(path) {
+                eprintln!("Failed to write {}: {}", path, e);
+                return ExitCode::FAILURE;
+            }
+            println!("Wrote maze {} to {}", seed, path);
+        }
+        None => println!("{}", maze.to_map_string()),
+    }
+    ExitCode::SUCCESS
+}
+
+fn fuzz(first_seed: u64, count: u64) -> ExitCode {
+    let mut failures = 0;
+    for seed in first_seed..first_seed + count {
+        let maze = generate_maze(&mut GameRng::new(seed));
+        let problems = validate_maze(&maze).problems();
+        if problems.is_empty() {
+            continue;
+        }
+        failures += 1;
+        println!("seed {}: {} problem(s)", seed, problems.len());
+        for problem in problems {
+            println!("  {}", problem);
+        }
+    }
+    println!("{} of {} mazes ok", count - failures, count);
+    if failures == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,19 @@
+// Authoritative server for a two-player LAN game: Pac-Man against a player-steered Blinky
+// Usage: cargo run --bin pacman-server -- [address] [seed]
+// Then start two clients with PACMAN_CONNECT=<address> cargo run
+use pacman_bevy::prelude::*;
+
+fn main() {
+    // No window and so no LogPlugin here, status lines still go to the terminal
+    bevy::log::tracing_subscriber::fmt().with_max_level(bevy::log::Level::INFO).init();
+
+    let mut args = std::env::args().skip(1);
+    let address = args.next().unwrap_or_else(|| DEFAULT_SERVER_ADDRESS.to_string());
+    let seed: u64 = args.next().and_then(|arg| arg.parse().ok()).unwrap_or(0);
+
+    let result = GameServer::bind(&address, seed).and_then(|mut server| server.run());
+    if let Err(e) = result {
+        eprintln!("Server on {} stopped: {}", address, e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,146 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+
+// Events
+// A ghost that was not frightened caught Pac-Man
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PacmanDeathEvent {
+    pub ghost: Entity,
+}
+
+// Pac-Man caught a frightened ghost
+#[derive(Event, Debug, Clone, Copy)]
+pub struct GhostEatenEvent {
+    pub ghost: Entity,
+    pub name: GhostName,
+}
+
+// Tile an actor occupies this tick and the one it occupied last tick
+#[derive(Component, Default)]
+pub struct TileOccupancy {
+    pub current: IVec2,
+    pub previous: IVec2,
+}
+
+impl TileOccupancy {
+    pub fn new(position: Vec2) -> Self {
+        let tile = world_to_tile(position);
+        TileOccupancy {
+            current: tile,
+            previous: tile,
+        }
+    }
+
+    // Both actors moved through each other between two ticks
+    pub fn swapped_with(&self, other: &TileOccupancy) -> bool {
+        self.current == other.previous && self.previous == other.current && self.current != self.previous
+    }
+}
+
+// Constants
+const GHOST_RADIUS: f32 = TILE_SIZE * 0.4;
+
+// Track which tile every actor is on, run after movement
+pub fn update_tile_occupancy_system(mut query: Query<(&mut TileOccupancy, &Transform)>) {
+    for (mut occupancy, transform) in query.iter_mut() {
+        occupancy.previous = occupancy.current;
+        occupancy.current = world_to_tile(transform.translation.truncate());
+    }
+}
+
+// Check Pac-Man against every ghost by tile, distance and tile swaps
+pub fn pacman_ghost_collision_system(
+    pacman_query: Query<(&Pacman, &Transform, &TileOccupancy)>,
+    ghost_query: Query<(Entity, &Ghost, &Transform, &TileOccupancy), Without<Pacman>>,
+    mut death_events: EventWriter<PacmanDeathEvent>,
+    mut eaten_events: EventWriter<GhostEatenEvent>,
+) {
+    for (pacman, pacman_transform, pacman_tile) in pacman_query.iter() {
+        let pacman_position = pacman_transform.translation.truncate();
+        for (ghost_entity, ghost, ghost_transform, ghost_tile) in ghost_query.iter() {
+            // Eyes pass straight through Pac-Man
+            if ghost.mode == GhostMode::Eaten {
+                continue;
+            }
+
+            let ghost_position = ghost_transform.translation.truncate();
+            let touching = ghost_position.distance(pacman_position) < pacman.radius + GHOST_RADIUS;
+            let same_tile = ghost_tile.current == pacman_tile.current;
+            if !touching && !same_tile && !pacman_tile.swapped_with(ghost_tile) {
+                continue;
+            }
+
+            if ghost.mode == GhostMode::Frightened {
+                eaten_events.send(GhostEatenEvent { ghost: ghost_entity, name: ghost.name });
+            } else {
+                death_events.send(PacmanDeathEvent { ghost: ghost_entity });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Pac-Man on one tile and a ghost on another, both standing on their tile centers
+    fn build_world(pacman_tile: (usize, usize), ghost_tile: (usize, usize), mode: GhostMode) -> (World, Entity, Entity) {
+        let mut world = World::new();
+        world.init_resource::<Events<PacmanDeathEvent>>();
+        world.init_resource::<Events<GhostEatenEvent>>();
+        let place = |(x, y): (usize, usize)| {
+            let position = tile_to_world(x, y);
+            (Transform::from_translation(position.extend(0.0)), TileOccupancy::new(position))
+        };
+        let pacman = world.spawn((Pacman::new(Vec2::ZERO, Entity::PLACEHOLDER, None), place(pacman_tile))).id();
+        let ghost = world.spawn((Ghost::new(GhostName::Blinky, Entity::PLACEHOLDER, mode), place(ghost_tile))).id();
+        (world, pacman, ghost)
+    }
+
+    // Jump both actors to new tiles in one tick, then check for collisions
+    fn step(world: &mut World, (pacman, pacman_to): (Entity, (usize, usize)), (ghost, ghost_to): (Entity, (usize, usize))) {
+        for (entity, (x, y)) in [(pacman, pacman_to), (ghost, ghost_to)] {
+            world.get_mut::<Transform>(entity).unwrap().translation = tile_to_world(x, y).extend(0.0);
+        }
+        let mut schedule = Schedule::default();
+        schedule.add_systems((update_tile_occupancy_system, pacman_ghost_collision_system).chain());
+        schedule.run(world);
+    }
+
+    #[test]
+    fn swapped_tiles_count_as_a_hit() {
+        let mut occupancy = TileOccupancy::new(tile_to_world(5, 1));
+        occupancy.current = IVec2::new(6, 1);
+        let mut other = TileOccupancy::new(tile_to_world(6, 1));
+        other.current = IVec2::new(5, 1);
+        assert!(occupancy.swapped_with(&other));
+        assert!(other.swapped_with(&occupancy));
+
+        // Standing still next to each other, or one following the other, isn't a swap
+        let still = TileOccupancy::new(tile_to_world(6, 1));
+        assert!(!TileOccupancy::new(tile_to_world(5, 1)).swapped_with(&still));
+        let mut following = TileOccupancy::new(tile_to_world(6, 1));
+        following.current = IVec2::new(7, 1);
+        assert!(!occupancy.swapped_with(&following));
+    }
+
+    #[test]
+    fn passing_through_a_ghost_kills_pacman() {
+        // Neither ends up on the other's tile or close enough to touch
+        let (mut world, pacman, ghost) = build_world((5, 1), (6, 1), GhostMode::Chase);
+        step(&mut world, (pacman, (6, 1)), (ghost, (5, 1)));
+        assert_eq!(world.resource::<Events<PacmanDeathEvent>>().len(), 1);
+
+        let (mut world, pacman, ghost) = build_world((5, 1), (6, 1), GhostMode::Frightened);
+        step(&mut world, (pacman, (6, 1)), (ghost, (5, 1)));
+        assert_eq!(world.resource::<Events<GhostEatenEvent>>().len(), 1);
+        assert!(world.resource::<Events<PacmanDeathEvent>>().is_empty());
+    }
+
+    #[test]
+    fn following_a_ghost_is_safe() {
+        let (mut world, pacman, ghost) = build_world((4, 1), (6, 1), GhostMode::Chase);
+        step(&mut world, (pacman, (5, 1)), (ghost, (7, 1)));
+        assert!(world.resource::<Events<PacmanDeathEvent>>().is_empty());
+    }
+}
@@ -0,0 +1,430 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::time::Duration;
+
+// Anything that can steer Pac-Man: the keyboard, a recorded replay or a bot
+// Called once per frame; Stop means "no input this frame"
+pub trait Controller: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn decide(&mut self, snapshot: &GameSnapshot) -> PacManDirection;
+
+    // Building the node graph every frame isn't free, so only controllers that plan routes ask for it
+    fn needs_graph(&self) -> bool {
+        false
+    }
+}
+
+// Whoever is steering Pac-Man right now
+#[derive(Resource)]
+pub struct PacmanController(pub Box<dyn Controller>);
+
+impl PacmanController {
+    // PACMAN_REPLAY=<file> plays back a replay, PACMAN_CONTROLLER=bot hands over to the bot
+    pub fn from_env() -> Self {
+        if let Ok(path) = std::env::var("PACMAN_REPLAY") {
+            match ReplayController::load(&path) {
+                Ok(replay) => return PacmanController(Box::new(replay)),
+                Err(e) => eprintln!("Failed to load replay {}: {}", path, e),
+            }
+        }
+        match std::env::var("PACMAN_CONTROLLER").as_deref() {
+            Ok("bot") => PacmanController(Box::new(GreedyBot)),
+            _ => PacmanController(Box::new(HumanController)),
+        }
+    }
+}
+
+// Where an actor is in the node graph, by maze cell so it doesn't depend on entity ids
+#[derive(Debug, Clone, Copy)]
+pub struct ActorView {
+    pub tile: IVec2,
+    pub position: Vec2,
+    pub node: (usize, usize),           // Node last left, or stood on
+    pub target: Option<(usize, usize)>, // Node being walked to
+    pub direction: PacManDirection,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GhostView {
+    pub name: GhostName,
+    pub mode: GhostMode,
+    pub actor: ActorView,
+}
+
+// Edge out of a node that Pac-Man may use
+#[derive(Debug, Clone, Copy)]
+pub struct GraphEdge {
+    pub direction: PacManDirection,
+    pub to: (usize, usize),
+    pub length: usize,
+}
+
+// Everything a controller gets to see when deciding
+#[derive(Debug, Clone)]
+pub struct GameSnapshot {
+    pub input: PacManDirection, // What the keyboard is pressing, Stop if nothing
+    pub elapsed: Duration,      // Game time since the last snapshot
+    pub pacman: ActorView,
+    pub facing: PacManDirection,
+    pub ghosts: Vec<GhostView>,
+    pub pellets: HashMap<(usize, usize), PelletKind>,
+    pub width: usize, // Maze width in tiles, where tunnels wrap
+    pub graph: HashMap<(usize, usize), Vec<GraphEdge>>, // Empty unless filled in with with_graph
+    pub score: u32,
+    pub lives: u32,
+    pub level: u32,
+}
+
+impl GameSnapshot {
+    pub fn capture<'a>(
+        input: PacManDirection,
+        pacman: (&Pacman, &Transform),
+        ghosts: impl Iterator<Item = (&'a Ghost, &'a Transform)>,
+        pellet_group: &PelletGroup,
+        maze: &Maze,
+        stats: &GameStats,
+        node_query: &Query<&MapNode>,
+    ) -> Self {
+        let tile_of = |entity: Entity| node_query.get(entity).map(|node| node.tile()).unwrap_or_default();
+        let view = |node: Entity, target: Option<Entity>, direction: PacManDirection, position: Vec2| ActorView {
+            tile: world_to_tile(position),
+            position,
+            node: tile_of(node),
+            target: target.map(tile_of),
+            direction,
+        };
+
+        let (pacman, pacman_transform) = pacman;
+        GameSnapshot {
+            input,
+            elapsed: Duration::ZERO,
+            pacman: view(
+                pacman.current_node,
+                pacman.target_node,
+                pacman.node_direction,
+                pacman_transform.translation.truncate(),
+            ),
+            facing: pacman.facing,
+            ghosts: ghosts
+                .map(|(ghost, transform)| GhostView {
+                    name: ghost.name,
+                    mode: ghost.mode,
+                    actor: view(
+                        ghost.current_node,
+                        ghost.target_node,
+                        ghost.node_direction,
+                        transform.translation.truncate(),
+                    ),
+                })
+                .collect(),
+            pellets: pellet_group
+                .tiles()
+                .map(|tile| (tile, maze.pellets.get(&tile).copied().unwrap_or(PelletKind::Pellet)))
+                .collect(),
+            width: maze.width(),
+            graph: HashMap::new(),
+            score: stats.score,
+            lives: stats.lives,
+            level: stats.level,
+        }
+    }
+
+    // The edges Pac-Man may use out of every node
+    pub fn with_graph(mut self, node_query: &Query<&MapNode>) -> Self {
+        let tile_of = |entity: Entity| node_query.get(entity).map(|node| node.tile()).unwrap_or_default();
+        self.graph = node_query
+            .iter()
+            .map(|node| {
+                let edges = PacManDirection::ALL
+                    .into_iter()
+                    .filter_map(|direction| {
+                        let edge = node.edge(direction).filter(|edge| edge.allows(ActorKind::Pacman))?;
+                        Some(GraphEdge {
+                            direction,
+                            to: tile_of(edge.to),
+                            length: edge.length,
+                        })
+                    })
+                    .collect();
+                (node.tile(), edges)
+            })
+            .collect();
+        self
+    }
+}
+
+// Arrow keys
+pub struct HumanController;
+
+impl Controller for HumanController {
+    fn name(&self) -> &'static str {
+        "human"
+    }
+
+    fn decide(&mut self, snapshot: &GameSnapshot) -> PacManDirection {
+        snapshot.input
+    }
+}
+
+// Plays back one recorded direction per simulation tick, then lets go
+// Ticks are counted from game time, so the replay doesn't depend on the frame rate
+pub struct ReplayController {
+    inputs: Vec<PacManDirection>,
+    index: usize,
+    elapsed: Duration,
+}
+
+impl ReplayController {
+    pub fn new(inputs: Vec<PacManDirection>) -> Self {
+        ReplayController {
+            inputs,
+            index: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    // One direction per line: Up, Down, Left, Right or Stop
+    pub fn load(path: &str) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let inputs = contents
+            .lines()
+            .map(|line| match line.trim() {
+                "Up" => PacManDirection::Up,
+                "Down" => PacManDirection::Down,
+                "Left" => PacManDirection::Left,
+                "Right" => PacManDirection::Right,
+                _ => PacManDirection::Stop,
+            })
+            .collect();
+        Ok(Self::new(inputs))
+    }
+}
+
+impl Controller for ReplayController {
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+
+    // A frame covering several ticks plays the last direction pressed in them, one between ticks plays nothing
+    fn decide(&mut self, snapshot: &GameSnapshot) -> PacManDirection {
+        self.elapsed += snapshot.elapsed;
+        let tick = (self.elapsed.as_nanos() * TICKS_PER_SECOND as u128 / 1_000_000_000) as usize;
+        let end = tick.min(self.inputs.len()).max(self.index);
+        let direction = self.inputs[self.index..end]
+            .iter()
+            .rev()
+            .copied()
+            .find(|&direction| direction != PacManDirection::Stop)
+            .unwrap_or(PacManDirection::Stop);
+        self.index = end;
+        direction
+    }
+}
+
+// Heads for the closest pellet along the node graph, never through tiles near a dangerous ghost
+pub struct GreedyBot;
+
+// Constants
+const DANGER_TILES: i32 = 3; // How close to a ghost counts as dangerous
+
+impl GreedyBot {
+    // Tiles near ghosts that can kill Pac-Man
+    fn danger_tiles(snapshot: &GameSnapshot) -> HashSet<IVec2> {
+        let mut danger = HashSet::new();
+        for ghost in snapshot.ghosts.iter() {
+            if !matches!(ghost.mode, GhostMode::Scatter | GhostMode::Chase | GhostMode::LeavingHouse) {
+                continue;
+            }
+            for dx in -DANGER_TILES..=DANGER_TILES {
+                for dy in -DANGER_TILES..=DANGER_TILES {
+                    if dx.abs() + dy.abs() <= DANGER_TILES {
+                        danger.insert(ghost.actor.tile + IVec2::new(dx, dy));
+                    }
+                }
+            }
+        }
+        danger
+    }
+
+    // Cells along an edge, not counting the node it starts from; tunnels wrap across the maze
+    fn edge_cells(from: (usize, usize), edge: &GraphEdge, width: usize) -> impl Iterator<Item = IVec2> {
+        let (dx, dy) = edge.direction.grid_offset();
+        let start = IVec2::new(from.0 as i32, from.1 as i32);
+        let step = IVec2::new(dx as i32, dy as i32);
+        (1..=edge.length as i32).map(move |k| {
+            let cell = start + step * k;
+            IVec2::new(cell.x.rem_euclid(width as i32), cell.y)
+        })
+    }
+
+    // Run away: the way out of the node whose far end is furthest from every dangerous ghost
+    fn flee(snapshot: &GameSnapshot, from: (usize, usize)) -> PacManDirection {
+        let threats: Vec<IVec2> = snapshot
+            .ghosts
+            .iter()
+            .filter(|ghost| matches!(ghost.mode, GhostMode::Scatter | GhostMode::Chase))
+            .map(|ghost| ghost.actor.tile)
+            .collect();
+        let distance_to_threat = |tile: (usize, usize)| {
+            let tile = IVec2::new(tile.0 as i32, tile.1 as i32);
+            threats.iter().map(|threat| (*threat - tile).abs().element_sum()).min().unwrap_or(i32::MAX)
+        };
+        snapshot
+            .graph
+            .get(&from)
+            .into_iter()
+            .flatten()
+            .max_by_key(|edge| distance_to_threat(edge.to))
+            .map_or(PacManDirection::Stop, |edge| edge.direction)
+    }
+}
+
+impl Controller for GreedyBot {
+    fn name(&self) -> &'static str {
+        "bot"
+    }
+
+    fn needs_graph(&self) -> bool {
+        true
+    }
+
+    fn decide(&mut self, snapshot: &GameSnapshot) -> PacManDirection {
+        let danger = Self::danger_tiles(snapshot);
+        let pacman = &snapshot.pacman;
+
+        // Plan from the node we are about to reach; turn back if a ghost is between us and it
+        let from = pacman.target.unwrap_or(pacman.node);
+        let ahead = IVec2::new(from.0 as i32, from.1 as i32);
+        let steps = (ahead - pacman.tile).abs().element_sum();
+        if pacman.target.is_some() {
+            let (dx, dy) = pacman.direction.grid_offset();
+            let step = IVec2::new(dx as i32, dy as i32);
+            let blocked = snapshot.ghosts.iter().any(|ghost| {
+                matches!(ghost.mode, GhostMode::Scatter | GhostMode::Chase)
+                    && (1..=steps).any(|k| pacman.tile + step * k == ghost.actor.tile)
+            });
+            if blocked {
+                return pacman.direction.opposite();
+            }
+        }
+
+        // Dijkstra by tiles, remembering the first turn taken out of the start node
+        let mut best: HashMap<(usize, usize), usize> = HashMap::from([(from, steps as usize)]);
+        let mut first_turn: HashMap<(usize, usize), PacManDirection> = HashMap::new();
+        let mut open = BinaryHeap::from([(Reverse(steps as usize), from)]);
+        while let Some((Reverse(distance), node)) = open.pop() {
+            if best.get(&node).is_some_and(|&known| distance > known) {
+                continue;
+            }
+            for edge in snapshot.graph.get(&node).into_iter().flatten() {
+                let cells: Vec<IVec2> = Self::edge_cells(node, edge, snapshot.width).collect();
+                if cells.iter().any(|cell| danger.contains(cell)) {
+                    continue;
+                }
+                let first = first_turn.get(&node).copied().unwrap_or(edge.direction);
+                let has_pellet = cells
+                    .iter()
+                    .any(|cell| cell.x >= 0 && cell.y >= 0 && snapshot.pellets.contains_key(&(cell.x as usize, cell.y as usize)));
+                if has_pellet {
+                    return first;
+                }
+                let next_distance = distance + edge.length;
+                if best.get(&edge.to).is_none_or(|&known| next_distance < known) {
+                    best.insert(edge.to, next_distance);
+                    first_turn.insert(edge.to, first);
+                    open.push((Reverse(next_distance), edge.to));
+                }
+            }
+        }
+
+        Self::flee(snapshot, from)
+    }
+}
+
+// Arrow key currently held, first one wins
+pub fn keyboard_direction(keyboard_input: &ButtonInput<KeyCode>) -> PacManDirection {
+    if keyboard_input.pressed(KeyCode::ArrowUp) {
+        PacManDirection::Up
+    } else if keyboard_input.pressed(KeyCode::ArrowDown) {
+        PacManDirection::Down
+    } else if keyboard_input.pressed(KeyCode::ArrowLeft) {
+        PacManDirection::Left
+    } else if keyboard_input.pressed(KeyCode::ArrowRight) {
+        PacManDirection::Right
+    } else {
+        PacManDirection::Stop
+    }
+}
+
+// F3 swaps between playing yourself and watching the bot
+pub fn toggle_controller_system(keyboard_input: Res<ButtonInput<KeyCode>>, mut controller: ResMut<PacmanController>) {
+    if !keyboard_input.just_pressed(KeyCode::F3) {
+        return;
+    }
+    controller.0 = if controller.0.name() == "bot" {
+        Box::new(HumanController)
+    } else {
+        Box::new(GreedyBot)
+    };
+    info!("Pac-Man controlled by {}", controller.0.name());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PacManDirection::*;
+
+    fn snapshot(elapsed: Duration) -> GameSnapshot {
+        let actor = ActorView { tile: IVec2::ZERO, position: Vec2::ZERO, node: (0, 0), target: None, direction: Stop };
+        GameSnapshot {
+            input: Stop,
+            elapsed,
+            pacman: actor,
+            facing: Left,
+            ghosts: Vec::new(),
+            pellets: HashMap::new(),
+            width: 5,
+            graph: HashMap::new(),
+            score: 0,
+            lives: 3,
+            level: 1,
+        }
+    }
+
+    fn play(frame: Duration, frames: usize) -> Vec<PacManDirection> {
+        let mut replay = ReplayController::new(vec![Up, Stop, Left, Stop, Down, Right]);
+        (0..frames).map(|_| replay.decide(&snapshot(frame))).collect()
+    }
+
+    #[test]
+    fn replay_follows_ticks_not_frames() {
+        let tick = Duration::from_secs_f32(1.0 / TICKS_PER_SECOND);
+        assert_eq!(play(tick, 7), vec![Up, Stop, Left, Stop, Down, Right, Stop]);
+        // Slow frames keep the latest direction of the ticks they cover
+        assert_eq!(play(tick * 3, 3), vec![Left, Right, Stop]);
+        // Fast frames see each tick once
+        assert_eq!(play(tick / 2, 6), vec![Stop, Up, Stop, Stop, Stop, Left]);
+    }
+
+    #[test]
+    fn bot_looks_along_tunnel_wraps() {
+        // From (4, 1) the way Right wraps straight to (0, 1)
+        let edge = GraphEdge { direction: Right, to: (0, 1), length: 1 };
+        assert_eq!(GreedyBot::edge_cells((4, 1), &edge, 5).collect::<Vec<_>>(), vec![IVec2::new(0, 1)]);
+        let edge = GraphEdge { direction: Left, to: (3, 1), length: 2 };
+        assert_eq!(GreedyBot::edge_cells((0, 1), &edge, 5).collect::<Vec<_>>(), vec![IVec2::new(4, 1), IVec2::new(3, 1)]);
+
+        // A pellet just past the tunnel mouth is worth heading for
+        let mut snapshot = snapshot(Duration::ZERO);
+        snapshot.pacman.node = (4, 1);
+        snapshot.pacman.tile = IVec2::new(4, 1);
+        snapshot.pellets.insert((0, 1), PelletKind::Pellet);
+        snapshot.graph.insert((4, 1), vec![
+            GraphEdge { direction: Left, to: (2, 1), length: 2 },
+            GraphEdge { direction: Right, to: (0, 1), length: 1 },
+        ]);
+        assert_eq!(GreedyBot.decide(&snapshot), Right);
+    }
+}
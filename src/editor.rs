@@ -0,0 +1,287 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use bevy::window::PrimaryWindow;
+
+// What a left click paints; right click always paints a wall
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Brush {
+    Wall,
+    Path,
+    Node,
+    Door,
+    Tunnel,
+    Pellet,
+    PowerPellet,
+    PacmanSpawn,
+}
+
+impl Brush {
+    // Number keys 1-8 pick a brush
+    const KEYS: [(KeyCode, Brush); 8] = [
+        (KeyCode::Digit1, Brush::Wall),
+        (KeyCode::Digit2, Brush::Path),
+        (KeyCode::Digit3, Brush::Node),
+        (KeyCode::Digit4, Brush::Door),
+        (KeyCode::Digit5, Brush::Tunnel),
+        (KeyCode::Digit6, Brush::Pellet),
+        (KeyCode::Digit7, Brush::PowerPellet),
+        (KeyCode::Digit8, Brush::PacmanSpawn),
+    ];
+
+    fn paint(&self, maze: &mut Maze, x: usize, y: usize) {
+        match self {
+            Brush::Wall => maze.set_cell(x, y, MazeCell::Empty),
+            Brush::Path => maze.set_cell(x, y, MazeCell::Path),
+            Brush::Node => maze.set_cell(x, y, MazeCell::Node),
+            Brush::Door => maze.set_cell(x, y, MazeCell::Door),
+            Brush::Tunnel => maze.set_cell(x, y, MazeCell::Tunnel),
+            Brush::Pellet | Brush::PowerPellet => {
+                // Pellets need somewhere to sit
+                if !matches!(maze.cell(x, y), MazeCell::Node | MazeCell::Path) {
+                    maze.set_cell(x, y, MazeCell::Path);
+                }
+                let kind = if *self == Brush::Pellet {
+                    PelletKind::Pellet
+                } else {
+                    PelletKind::PowerPellet
+                };
+                maze.set_pellet(x, y, Some(kind));
+            }
+            Brush::PacmanSpawn => {
+                maze.set_cell(x, y, MazeCell::Node);
+                maze.set_pellet(x, y, None);
+                maze.pacman_spawn = Some((x, y));
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct MazeEditor {
+    pub brush: Brush,
+    pub path: String, // File the maze is saved to, see MazeSource::save_path
+    pub report: MazeReport,
+    tiles: HashMap<(usize, usize), Entity>,
+}
+
+// Everything the editor spawns, cleared when leaving
+#[derive(Component)]
+pub struct EditorEntity;
+
+#[derive(Component)]
+pub struct EditorStatusText;
+
+// Constants
+const EDITOR_Z: f32 = 5.0;
+
+// F2 toggles between the game and the editor
+pub fn toggle_editor_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F2) {
+        return;
+    }
+    match state.get() {
+        GameState::Editor => next_state.set(GameState::Ready),
+        GameState::Ready | GameState::Playing => next_state.set(GameState::Editor),
+        _ => {}
+    }
+}
+
+// Cover the board with one sprite per maze cell
+pub fn setup_editor(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    maze: Res<Maze>,
+    source: Res<MazeSource>,
+    theme: Res<Theme>,
+) {
+    let mut tiles = HashMap::new();
+    for y in 0..maze.height() {
+        for x in 0..maze.width() {
+            let position = tile_to_world(x, y);
+            let tile = commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: theme.editor_cell(maze.cell(x, y)),
+                        custom_size: Some(Vec2::splat(TILE_SIZE - 1.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(position.extend(EDITOR_Z)),
+                    ..default()
+                },
+                EditorEntity,
+            )).id();
+            tiles.insert((x, y), tile);
+        }
+    }
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: 16.0,
+                color: theme.hud.text,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(36.0),
+            left: Val::Px(8.0),
+            ..default()
+        }),
+        EditorStatusText,
+        EditorEntity,
+    ));
+
+    commands.insert_resource(MazeEditor {
+        brush: Brush::Wall,
+        path: source.save_path(),
+        report: validate_maze(&maze),
+        tiles,
+    });
+}
+
+pub fn teardown_editor(mut commands: Commands, query: Query<Entity, With<EditorEntity>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<MazeEditor>();
+}
+
+// Pick brushes, paint with the mouse and save with S
+pub fn editor_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut editor: ResMut<MazeEditor>,
+    mut maze: ResMut<Maze>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+) {
+    for (key, brush) in Brush::KEYS {
+        if keyboard_input.just_pressed(key) {
+            editor.brush = brush;
+        }
+    }
+
+    // Recompute every node from the walls and paths
+    if keyboard_input.just_pressed(KeyCode::KeyN) {
+        maze.detect_nodes();
+        editor.report = validate_maze(&maze);
+    }
+
+    if keyboard_input.just_pressed(KeyCode::KeyS) {
+        match maze.write_map(&editor.path) {
+            Ok(()) => info!("Saved maze to {}", editor.path),
+            Err(e) => eprintln!("Failed to save maze to {}: {}", editor.path, e),
+        }
+    }
+
+    let brush = if mouse_input.pressed(MouseButton::Left) {
+        editor.brush
+    } else if mouse_input.pressed(MouseButton::Right) {
+        Brush::Wall
+    } else {
+        return;
+    };
+
+    // Cursor to world to maze cell
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    let Some((camera, camera_transform)) = camera_query.iter().next() else {
+        return;
+    };
+    let Some(world_position) = camera.viewport_to_world_2d(camera_transform, cursor) else {
+        return;
+    };
+    let tile = world_to_tile(world_position);
+    if tile.x < 0 || tile.y < 0 || tile.x as usize >= maze.width() || tile.y as usize >= maze.height() {
+        return;
+    }
+    let (x, y) = (tile.x as usize, tile.y as usize);
+
+    // Only touch the maze when something actually changes so validation stays cheap
+    let mut painted = maze.clone();
+    brush.paint(&mut painted, x, y);
+    if painted != *maze {
+        *maze = painted;
+        editor.report = validate_maze(&maze);
+    }
+}
+
+// Recolor tiles and refresh the status line after edits
+pub fn editor_refresh_system(
+    maze: Res<Maze>,
+    editor: Res<MazeEditor>,
+    theme: Res<Theme>,
+    mut tile_query: Query<&mut Sprite, With<EditorEntity>>,
+    mut text_query: Query<&mut Text, With<EditorStatusText>>,
+) {
+    if !maze.is_changed() && !editor.is_changed() && !theme.is_changed() {
+        return;
+    }
+    for (&(x, y), &tile) in editor.tiles.iter() {
+        if let Ok(mut sprite) = tile_query.get_mut(tile) {
+            sprite.color = theme.editor_cell(maze.cell(x, y));
+        }
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!(
+            "EDITOR  brush {:?} (1-8)  N auto nodes  S save  F2 play\n{}",
+            editor.brush,
+            editor.report.summary()
+        );
+    }
+}
+
+// Draw pellets and the live neighbor graph over the tiles
+pub fn editor_gizmo_system(maze: Res<Maze>, editor: Res<MazeEditor>, theme: Res<Theme>, mut gizmos: Gizmos) {
+    for (&(x, y), kind) in maze.pellets.iter() {
+        let radius = match kind {
+            PelletKind::Pellet => TILE_SIZE * 0.1,
+            PelletKind::PowerPellet => TILE_SIZE * 0.3,
+        };
+        gizmos.circle_2d(tile_to_world(x, y), radius, theme.pellet(*kind));
+    }
+    if let Some((x, y)) = maze.pacman_spawn {
+        gizmos.circle_2d(tile_to_world(x, y), TILE_SIZE * 0.45, theme.pacman);
+    }
+
+    let connected: HashSet<(usize, usize)> = editor
+        .report
+        .components
+        .first()
+        .map(|component| component.iter().copied().collect())
+        .unwrap_or_default();
+
+    for (x, y) in maze.node_cells() {
+        let position = tile_to_world(x, y);
+        // Nodes cut off from the main graph stand out
+        let color = if connected.contains(&(x, y)) { theme.node } else { theme.editor.disconnected };
+        gizmos.circle_2d(position, TILE_SIZE * 0.35, color);
+        // Right and Down only, so each link is drawn once
+        for direction in [PacManDirection::Right, PacManDirection::Down] {
+            // Tunnel wraps would cut right across the maze
+            if let Some(edge) = maze.trace_edge(x, y, direction).filter(|edge| !edge.wraps) {
+                let line_color = if edge.door { theme.door } else { theme.editor.link };
+                gizmos.line_2d(position, tile_to_world(edge.end.0, edge.end.1), line_color);
+            }
+        }
+    }
+}
+
+// Leaving the editor: throw away the old level and build it again from the edited maze
+type LevelEntityFilter = Or<(With<MapNode>, With<NodeQuad>, With<Pacman>, With<Ghost>, With<Fruit>)>;
+
+pub fn despawn_level(mut commands: Commands, query: Query<Entity, LevelEntityFilter>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
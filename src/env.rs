@@ -0,0 +1,349 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use bevy::ecs::schedule::ExecutorKind;
+use bevy::ecs::system::{RunSystemOnce, SystemState};
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Gym-style wrapper for training agents: no window, no sound, one fixed tick per step
+//   let mut env = PacmanEnv::new();
+//   let mut observation = env.reset(seed)?;
+//   loop { let (next, reward, done) = env.step(action)?; ... }
+// One app plays every episode; reset starts a new game in it rather than building another
+pub struct PacmanEnv {
+    app: App,
+    snapshot: SystemState<SnapshotParams<'static, 'static>>,
+    agent: AgentController,
+}
+
+type SnapshotParams<'w, 's> = (
+    Query<'w, 's, (&'static Pacman, &'static Transform)>,
+    Query<'w, 's, (&'static Ghost, &'static Transform)>,
+    Res<'w, PelletGroup>,
+    Res<'w, Maze>,
+    Res<'w, GameStats>,
+    Query<'w, 's, &'static MapNode>,
+);
+
+// What a cell of the observation grid holds
+pub const OBS_WALL: u8 = 0;
+pub const OBS_PATH: u8 = 1;
+pub const OBS_PELLET: u8 = 2;
+pub const OBS_POWER_PELLET: u8 = 3;
+pub const OBS_DOOR: u8 = 4;
+pub const OBS_HOUSE: u8 = 5;
+
+// Constants
+const STEP_SECONDS: f32 = 1.0 / TICKS_PER_SECOND; // One arcade tick per step
+const DEATH_PENALTY: f32 = 500.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ActorObservation {
+    pub tile: IVec2,
+    pub position: Vec2, // In tiles, fractional while between cells
+    pub direction: PacManDirection,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GhostObservation {
+    pub name: GhostName,
+    pub mode: GhostMode,
+    pub actor: ActorObservation,
+}
+
+#[derive(Debug, Clone)]
+pub struct Observation {
+    pub width: usize,
+    pub height: usize,
+    pub grid: Vec<u8>, // Row major, one OBS_* code per cell
+    pub pacman: ActorObservation,
+    pub ghosts: Vec<GhostObservation>,
+    pub score: u32,
+    pub lives: u32,
+    pub level: u32,
+}
+
+impl Observation {
+    fn new(snapshot: &GameSnapshot, maze: &Maze) -> Self {
+        let mut grid = Vec::with_capacity(maze.width() * maze.height());
+        for y in 0..maze.height() {
+            for x in 0..maze.width() {
+                let code = match (snapshot.pellets.get(&(x, y)), maze.cell(x, y)) {
+                    (Some(PelletKind::Pellet), _) => OBS_PELLET,
+                    (Some(PelletKind::PowerPellet), _) => OBS_POWER_PELLET,
+                    (None, MazeCell::Empty) => OBS_WALL,
+                    (None, MazeCell::Path | MazeCell::Node | MazeCell::Tunnel) => OBS_PATH,
+                    (None, MazeCell::Door) => OBS_DOOR,
+                    (None, MazeCell::Home) => OBS_HOUSE,
+                };
+                grid.push(code);
+            }
+        }
+
+        let origin = tile_to_world(0, 0);
+        let actor = |view: &ActorView| ActorObservation {
+            tile: view.tile,
+            position: Vec2::new(view.position.x - origin.x, origin.y - view.position.y) / TILE_SIZE,
+            direction: view.direction,
+        };
+
+        Observation {
+            width: maze.width(),
+            height: maze.height(),
+            grid,
+            pacman: actor(&snapshot.pacman),
+            ghosts: snapshot
+                .ghosts
+                .iter()
+                .map(|ghost| GhostObservation {
+                    name: ghost.name,
+                    mode: ghost.mode,
+                    actor: actor(&ghost.actor),
+                })
+                .collect(),
+            score: snapshot.score,
+            lives: snapshot.lives,
+            level: snapshot.level,
+        }
+    }
+
+    // Flat feature vector: the grid, then Pac-Man x, y, direction, then x, y, mode for each ghost
+    pub fn to_features(&self) -> Vec<f32> {
+        let mut features: Vec<f32> = self.grid.iter().map(|&code| code as f32).collect();
+        features.extend([
+            self.pacman.position.x,
+            self.pacman.position.y,
+            direction_index(self.pacman.direction) as f32,
+        ]);
+        for ghost in self.ghosts.iter() {
+            features.extend([
+                ghost.actor.position.x,
+                ghost.actor.position.y,
+                mode_index(ghost.mode) as f32,
+            ]);
+        }
+        features
+    }
+}
+
+pub fn direction_index(direction: PacManDirection) -> usize {
+    match direction {
+        PacManDirection::Up => 0,
+        PacManDirection::Down => 1,
+        PacManDirection::Left => 2,
+        PacManDirection::Right => 3,
+        PacManDirection::Stop => 4,
+    }
+}
+
+pub fn mode_index(mode: GhostMode) -> usize {
+    match mode {
+        GhostMode::Scatter => 0,
+        GhostMode::Chase => 1,
+        GhostMode::Frightened => 2,
+        GhostMode::Eaten => 3,
+        GhostMode::LeavingHouse => 4,
+        GhostMode::InHouse => 5,
+    }
+}
+
+// Plays whatever the agent picked for this step
+// Clones share the pick, so whoever steps the game keeps one and hands the other to PacmanController
+#[derive(Clone)]
+pub struct AgentController(Arc<Mutex<PacManDirection>>);
+
+impl AgentController {
+    pub fn new() -> Self {
+        AgentController(Arc::new(Mutex::new(PacManDirection::Stop)))
+    }
+
+    pub fn set(&self, direction: PacManDirection) {
+        *self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = direction;
+    }
+
+    // Plays this controller from now on and hands back a handle to steer it
+    pub fn install(app: &mut App) -> Self {
+        let agent = Self::new();
+        app.insert_resource(PacmanController(Box::new(agent.clone())));
+        agent
+    }
+}
+
+impl Default for AgentController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Controller for AgentController {
+    fn name(&self) -> &'static str {
+        "agent"
+    }
+
+    fn decide(&mut self, _snapshot: &GameSnapshot) -> PacManDirection {
+        *self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// Only the rules, stepped by hand at a fixed tick; also what the network server runs
+pub fn headless_app(seed: u64) -> App {
+    let mut app = unfinished_headless_app(seed);
+    app.finish();
+    app.cleanup();
+    app
+}
+
+// The headless app before its plugins are finished, for callers that add more of their own
+pub(crate) fn unfinished_headless_app(seed: u64) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .add_plugins((SimulationPlugin, TelemetryPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(STEP_SECONDS)))
+        .insert_resource(ButtonInput::<KeyCode>::default())
+        .insert_resource(GameRng::new(seed))
+        .insert_resource(PacmanController(Box::new(AgentController::new())))
+        .insert_resource(HighScoreTable::default());
+    // One thread keeps system order, and so every run of a seed, the same
+    app.edit_schedule(Update, |schedule| {
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+    });
+    app
+}
+
+impl PacmanEnv {
+    pub fn new() -> Self {
+        let mut app = headless_app(0);
+        let agent = AgentController::install(&mut app);
+        let snapshot = SystemState::new(app.world_mut());
+        app.update(); // Startup builds the level
+        PacmanEnv { app, snapshot, agent }
+    }
+
+    // Fresh game with the given seed, skipping the intro
+    pub fn reset(&mut self, seed: u64) -> Result<Observation, String> {
+        let world = self.app.world_mut();
+        world.resource_mut::<GameRng>().reseed(seed);
+        world.run_system_once(new_game_system);
+        *world.resource_mut::<StateTimer>() = StateTimer::new(0.0);
+        world.resource_mut::<NextState<GameState>>().set(GameState::Ready);
+        self.agent.set(PacManDirection::Stop);
+        self.run_until_playing();
+        self.observe()
+    }
+
+    // Advance one tick with the given direction; Stop keeps going the way Pac-Man is headed
+    // Ready and Dying pauses are skipped so every step is one the agent can act in
+    pub fn step(&mut self, action: PacManDirection) -> Result<(Observation, f32, bool), String> {
+        let (score, lives) = {
+            let stats = self.app.world().resource::<GameStats>();
+            (stats.score, stats.lives)
+        };
+        self.agent.set(action);
+        self.app.update();
+        self.run_until_playing();
+
+        let stats = self.app.world().resource::<GameStats>();
+        let mut reward = stats.score.saturating_sub(score) as f32;
+        if stats.lives < lives {
+            reward -= DEATH_PENALTY;
+        }
+        Ok((self.observe()?, reward, self.is_done()))
+    }
+
+    // Snapshot of the current game, e.g. to branch from it in a search
+    pub fn save(&mut self) -> Result<SaveGame, String> {
+        SaveGame::capture(self.app.world_mut())
+    }
+
+    // Jump back to a snapshot taken with save
+    pub fn restore(&mut self, save: &SaveGame) -> Result<Observation, String> {
+        save.restore(self.app.world_mut())?;
+        self.run_until_playing();
+        self.observe()
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(self.state(), GameState::NameEntry | GameState::GameOver)
+    }
+
+    fn state(&self) -> GameState {
+        *self.app.world().resource::<State<GameState>>().get()
+    }
+
+    fn run_until_playing(&mut self) {
+        // A state change only shows up on the next update, so look at what is queued too
+        loop {
+            let state = self.app.world().resource::<NextState<GameState>>();
+            let pending = matches!(state, NextState::Pending(_));
+            if !pending && (self.state() == GameState::Playing || self.is_done()) {
+                break;
+            }
+            self.app.update();
+        }
+    }
+
+    pub fn observe(&mut self) -> Result<Observation, String> {
+        let world = self.app.world();
+        let (pacman_query, ghost_query, pellet_group, maze, stats, node_query) = self.snapshot.get(world);
+        let pacman = pacman_query
+            .get_single()
+            .map_err(|_| "the environment has no Pac-Man, is the maze missing a spawn?".to_string())?;
+        let snapshot = GameSnapshot::capture(
+            PacManDirection::Stop,
+            pacman,
+            ghost_query.iter(),
+            &pellet_group,
+            &maze,
+            &stats,
+            &node_query,
+        );
+        Ok(Observation::new(&snapshot, &maze))
+    }
+}
+
+impl Default for PacmanEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    // Constants
+    const STEPS: usize = 600;
+    const ACTION_HOLD: usize = 15;
+
+    // Rewards and observations for an episode with actions drawn from the seed
+    fn play(env: &mut PacmanEnv, seed: u64) -> Vec<(Vec<f32>, f32, bool)> {
+        let mut episode = vec![(env.reset(seed).unwrap().to_features(), 0.0, false)];
+        let mut rng = GameRng::new(seed);
+        let mut action = PacManDirection::Stop;
+        for step in 0..STEPS {
+            if step % ACTION_HOLD == 0 {
+                action = PacManDirection::ALL[rng.gen_range(0..PacManDirection::ALL.len())];
+            }
+            let (observation, reward, done) = env.step(action).unwrap();
+            episode.push((observation.to_features(), reward, done));
+            if done {
+                break;
+            }
+        }
+        episode
+    }
+
+    #[test]
+    fn same_seed_same_episode() {
+        let first = play(&mut PacmanEnv::new(), 5);
+        assert!(first.iter().any(|&(_, reward, _)| reward > 0.0), "Pac-Man should score something");
+
+        // Again after another episode in the same app
+        let mut env = PacmanEnv::new();
+        play(&mut env, 9);
+        assert!(play(&mut env, 5) == first);
+    }
+}
@@ -0,0 +1,170 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use rand::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FruitKind {
+    Cherry,
+    Strawberry,
+    Peach,
+    Apple,
+    Melon,
+    Galaxian,
+    Bell,
+    Key,
+}
+
+impl FruitKind {
+    pub fn points(&self) -> u32 {
+        match self {
+            FruitKind::Cherry => 100,
+            FruitKind::Strawberry => 300,
+            FruitKind::Peach => 500,
+            FruitKind::Apple => 700,
+            FruitKind::Melon => 1000,
+            FruitKind::Galaxian => 2000,
+            FruitKind::Bell => 3000,
+            FruitKind::Key => 5000,
+        }
+    }
+
+}
+
+// Bonus fruit currently on the board
+#[derive(Component, Clone, Serialize, Deserialize)]
+pub struct Fruit {
+    pub kind: FruitKind,
+    pub timer: Timer,
+}
+
+// Pac-Man ate the bonus fruit
+#[derive(Event, Debug, Clone, Copy)]
+pub struct FruitEatenEvent {
+    pub kind: FruitKind,
+    pub points: u32,
+}
+
+// Constants
+const FRUIT_PELLET_THRESHOLDS: [usize; 2] = [70, 170];
+const FRUIT_MIN_SECONDS: f32 = 9.0;
+const FRUIT_MAX_SECONDS: f32 = 10.0;
+const FRUIT_HISTORY_LENGTH: usize = 7;
+
+// Tracks how many fruit have appeared this level and the fruit shown in the HUD
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct FruitTracker {
+    pub spawned_this_level: usize,
+    pub history: Vec<FruitKind>, // Oldest first, one entry per level reached
+}
+
+impl FruitTracker {
+    pub fn new() -> Self {
+        FruitTracker {
+            spawned_this_level: 0,
+            history: vec![level_spec(1).fruit],
+        }
+    }
+
+    // A new level starts: reset the spawn count and record its fruit
+    pub fn start_level(&mut self, level: u32) {
+        self.spawned_this_level = 0;
+        self.history.push(level_spec(level).fruit);
+        if self.history.len() > FRUIT_HISTORY_LENGTH {
+            self.history.remove(0);
+        }
+    }
+}
+
+impl Default for FruitTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Spawn the level's fruit once enough pellets have been eaten
+pub fn fruit_spawn_system(
+    mut commands: Commands,
+    mut tracker: ResMut<FruitTracker>,
+    stats: Res<GameStats>,
+    maze: Res<Maze>,
+    theme: Res<Theme>,
+    mut rng: ResMut<GameRng>,
+    fruit_query: Query<(), With<Fruit>>,
+) {
+    let Some(&threshold) = FRUIT_PELLET_THRESHOLDS.get(tracker.spawned_this_level) else {
+        return;
+    };
+    if stats.pellets_eaten < threshold || !fruit_query.is_empty() {
+        return;
+    }
+    let Some((x, y)) = maze.fruit_tile else {
+        return;
+    };
+
+    tracker.spawned_this_level += 1;
+    let kind = level_spec(stats.level).fruit;
+    let seconds = rng.gen_range(FRUIT_MIN_SECONDS..FRUIT_MAX_SECONDS);
+    spawn_fruit(
+        &mut commands,
+        &theme,
+        Fruit {
+            kind,
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        },
+        (x, y),
+    );
+}
+
+// Put a fruit on the board at a maze cell
+pub fn spawn_fruit(commands: &mut Commands, theme: &Theme, fruit: Fruit, (x, y): (usize, usize)) {
+    let position = tile_to_world(x, y);
+    let color = theme.fruit(fruit.kind);
+    commands.spawn((
+        fruit,
+        SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::splat(TILE_SIZE * 0.75)),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(position.extend(0.8)),
+            ..Default::default()
+        },
+    ));
+}
+
+// Fruit disappears if nobody eats it in time
+pub fn fruit_timer_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut fruit_query: Query<(Entity, &mut Fruit)>,
+) {
+    for (entity, mut fruit) in fruit_query.iter_mut() {
+        fruit.timer.tick(time.delta());
+        if fruit.timer.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// Pac-Man eats the fruit by standing on its tile
+pub fn fruit_eating_system(
+    mut commands: Commands,
+    mut stats: ResMut<GameStats>,
+    mut fruit_events: EventWriter<FruitEatenEvent>,
+    pacman_query: Query<&TileOccupancy, With<Pacman>>,
+    fruit_query: Query<(Entity, &Fruit, &Transform)>,
+) {
+    for occupancy in pacman_query.iter() {
+        for (entity, fruit, transform) in fruit_query.iter() {
+            if world_to_tile(transform.translation.truncate()) != occupancy.current {
+                continue;
+            }
+            let points = fruit.kind.points();
+            stats.score += points;
+            fruit_events.send(FruitEatenEvent { kind: fruit.kind, points });
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -1,105 +1,345 @@
 // Prelude + Other Crates
 use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Score and progress through the game
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct GameStats {
+    pub score: u32,
+    pub level: u32,
+    pub lives: u32,
+    pub pellets_eaten: usize, // This level
+    pub pellets_remaining: usize,
+}
+
+impl GameStats {
+    pub fn new() -> Self {
+        GameStats {
+            score: 0,
+            level: 1,
+            lives: STARTING_LIVES,
+            pellets_eaten: 0,
+            pellets_remaining: 0,
+        }
+    }
+
+    // Nothing has happened yet this game
+    pub fn is_new_game(&self) -> bool {
+        self.level == 1 && self.lives == STARTING_LIVES && self.pellets_eaten == 0
+    }
+}
+
+impl Default for GameStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Flow of a round
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum GameState {
+    #[default]
+    Title, // Picking a game mode
+    Ready, // Pause before play starts
+    Playing,
+    Dying,     // Death jingle before the round restarts
+    NameEntry, // Entering initials for a new high score
+    GameOver,  // High score table before a new game starts
+    Editor,    // Maze editor, the game is rebuilt when leaving
+}
+
+// How long the current Ready/Dying state lasts
+#[derive(Resource)]
+pub struct StateTimer(pub Timer);
+
+impl StateTimer {
+    pub fn new(seconds: f32) -> Self {
+        StateTimer(Timer::from_seconds(seconds, TimerMode::Once))
+    }
+}
+
+// Arcade frames an actor sits out, e.g. Pac-Man chewing a pellet
+// Kept fractional so it drains by time and behaves the same at any frame rate
+#[derive(Component, Default)]
+pub struct SkipTicks {
+    pub remaining: f32,
+}
+
+impl SkipTicks {
+    pub fn add(&mut self, ticks: u32) {
+        self.remaining += ticks as f32;
+    }
+
+    // Spend as much of the frame as needed sitting still, returns the seconds left to move
+    pub fn consume(&mut self, seconds: f32) -> f32 {
+        let stalled = (self.remaining / TICKS_PER_SECOND).min(seconds);
+        self.remaining = (self.remaining - stalled * TICKS_PER_SECOND).max(0.0);
+        seconds - stalled
+    }
+}
+
+// Constants
+pub const TICKS_PER_SECOND: f32 = 60.0; // Arcade frame rate
+pub const STARTING_LIVES: u32 = 3;
+pub const INTRO_SECONDS: f32 = 4.2; // Length of the intro jingle at game start
+const READY_SECONDS: f32 = 2.0;
+const DYING_SECONDS: f32 = 1.6;
+
+// Every pellet on the board was eaten
+#[derive(Event, Debug, Clone, Copy)]
+pub struct LevelClearedEvent {
+    pub level: u32,
+}
 
 // User input system
+// The controller (keyboard, replay or bot) picks a direction from a snapshot of the game
+#[allow(clippy::too_many_arguments)]
 pub fn pacman_input_system(
+    time: Res<Time>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut pacman_query: Query<&mut Pacman>,
+    mut controller: ResMut<PacmanController>,
+    stats: Res<GameStats>,
+    maze: Res<Maze>,
+    pellet_group: Res<PelletGroup>,
+    mut pacman_query: Query<(&mut Pacman, &Transform)>,
+    ghost_query: Query<(&Ghost, &Transform)>,
     node_query: Query<&MapNode>,
 ) {
-    for mut pacman in pacman_query.iter_mut() {
-        let new_direction = if keyboard_input.pressed(KeyCode::ArrowUp) {
-            Some(PacManDirection::Up)
-        } else if keyboard_input.pressed(KeyCode::ArrowDown) {
-            Some(PacManDirection::Down)
-        } else if keyboard_input.pressed(KeyCode::ArrowLeft) {
-            Some(PacManDirection::Left)
-        } else if keyboard_input.pressed(KeyCode::ArrowRight) {
-            Some(PacManDirection::Right)
-        } else {
-            None
-        };
-        
-
-        if let Some(direction) = new_direction {
-            // If Pac-Man is currently stopped, start moving in the new direction
-            if pacman.node_direction == PacManDirection::Stop {
-                pacman.set_direction_and_target(direction, &node_query, false);
-                pacman.queued_direction = None; // Clear the queue since we're starting immediately
-                println!("New Direction: {:?}", pacman.node_direction);
-            // Reverse
-            } else if pacman.node_direction.opposite() == direction {
-                pacman.set_direction_and_target(direction, &node_query, true);
-                pacman.queued_direction = None;
-                println!("Reverse Input Detected{:?}", pacman.node_direction);
-            } else {
-                // Otherwise, queue the new direction to apply at the next node
-                pacman.queued_direction = Some(direction);
-            }
+    for (mut pacman, transform) in pacman_query.iter_mut() {
+        let mut snapshot = GameSnapshot::capture(
+            keyboard_direction(&keyboard_input),
+            (&pacman, transform),
+            ghost_query.iter(),
+            &pellet_group,
+            &maze,
+            &stats,
+            &node_query,
+        );
+        snapshot.elapsed = time.delta();
+        if controller.0.needs_graph() {
+            snapshot = snapshot.with_graph(&node_query);
+        }
+        let direction = controller.0.decide(&snapshot);
+        if direction == PacManDirection::Stop {
+            continue;
+        }
+        if pacman.target_node.is_some() && direction == pacman.node_direction.opposite() {
+            // Reversing is allowed anywhere along an edge
+            pacman.reverse(&node_query);
+            pacman.queued_direction = None;
+        } else if direction != pacman.node_direction {
+            // Otherwise, queue the new direction to apply at the next node
+            pacman.queued_direction = Some(direction);
         } else {
-            let old_direction = pacman.node_direction;
-            pacman.set_direction_and_target(old_direction, &node_query, false);
+            // Holding the current direction again cancels a turn that hasn't happened yet
+            pacman.queued_direction = None;
         }
     }
 }
 
 // Pac-Man Node Movement System
+// Walks along the current edge; every node reached within the frame gets a chance to turn
 pub fn pacman_node_based_movement_system(
-    time: Res<Time>, 
-    mut pacman_query: Query<(&mut Pacman, &mut Transform)>,
+    time: Res<Time>,
+    mut pacman_query: Query<(&mut Pacman, &mut Transform, &mut SkipTicks)>,
     node_query: Query<&MapNode>,
 ) {
-    for (mut pacman, mut transform) in pacman_query.iter_mut() {
-        // Calculate incremental movement based on direction and speed
-        let movement_distance = pacman.speed * time.delta_seconds();
-        let delta_movement = match pacman.node_direction {
-            PacManDirection::Up => Vec2::new(0.0, movement_distance),
-            PacManDirection::Down => Vec2::new(0.0, -movement_distance),
-            PacManDirection::Left => Vec2::new(-movement_distance, 0.0),
-            PacManDirection::Right => Vec2::new(movement_distance, 0.0),
-            PacManDirection::Stop => Vec2::ZERO,
+    for (mut pacman, mut transform, mut skip_ticks) in pacman_query.iter_mut() {
+        let mut remaining = pacman.speed * skip_ticks.consume(time.delta_seconds());
+        while remaining > 0.0 {
+            if pacman.target_node.is_none() && !pacman.leave_node(&node_query) {
+                break; // Dead end or wall ahead, wait for input
+            }
+            let to_go = pacman.edge_length - pacman.edge_progress;
+            if remaining < to_go {
+                pacman.edge_progress += remaining;
+                break;
+            }
+            remaining -= to_go;
+            pacman.arrive(&node_query);
+        }
+        transform.translation = pacman.position().extend(transform.translation.z);
+    }
+}
+
+// Eating makes Pac-Man miss a few ticks of movement, as set by the level table
+pub fn eating_stall_system(
+    mut pellet_events: EventReader<PelletEatenEvent>,
+    stats: Res<GameStats>,
+    mut pacman_query: Query<&mut SkipTicks, With<Pacman>>,
+) {
+    let spec = level_spec(stats.level);
+    for event in pellet_events.read() {
+        let ticks = match event.kind {
+            PelletKind::Pellet => spec.pellet_skip_ticks,
+            PelletKind::PowerPellet => spec.power_pellet_skip_ticks,
         };
+        for mut skip_ticks in pacman_query.iter_mut() {
+            skip_ticks.add(ticks);
+        }
+    }
+}
 
-        // Apply incremental movement based on `delta_movement`
-        let new_position = Vec2::new(transform.translation.x, transform.translation.y) + delta_movement;
-        transform.translation = Vec3::new(new_position.x, new_position.y, transform.translation.z);
-        
-        // Check if Pac-Man has overshot or reached the target node
-        if pacman.overshot_target(&transform, &node_query) {
-            if let Some(target_node) = pacman.target_node {
-                // Align Pac-Man exactly to the node's position
-                if let Ok(node) = node_query.get(target_node) {
-                    transform.translation = Vec3::new(node.position.x, node.position.y, transform.translation.z);
-                    pacman.current_node = target_node;
-                    pacman.node_position = node.position;
-
-                    // Apply queued direction if valid; otherwise, maintain the current direction or stop
-                    if let Some(queued_direction) = pacman.queued_direction {
-                        if pacman.valid_direction(queued_direction, &node_query) {
-                            pacman.set_direction_and_target(queued_direction, &node_query, false);
-                            pacman.queued_direction = None;
-                        }
-                    }
-
-                    // Set the next target based on current direction, or stop if there is none
-                    /*
-                    let new_target = pacman.get_new_target(pacman.node_direction, &node_query);
-                    if new_target != pacman.current_node {
-                        // pacman.current_node = new_target;
-                        pacman.target_node = new_target;
-                    } else {
-                        pacman.node_direction = PacManDirection::Stop;
-                    }
-                    
-                    if !pacman.valid_direction(pacman.node_direction, &node_query) {
-                        pacman.node_direction = PacManDirection::Stop;
-                    }
-                    */
-                }
-            }
-        }/* else {
-            println!("Pacman has not overshot target");
-        }*/
+// Send Pac-Man and the ghosts back to their starting nodes
+pub fn reset_actors(
+    schedule: &mut GhostModeSchedule,
+    pacman_query: &mut Query<(&mut Pacman, &mut Transform), Without<Ghost>>,
+    ghost_query: &mut Query<(&mut Ghost, &mut Transform), Without<Pacman>>,
+    node_query: &Query<&MapNode>,
+) {
+    for (mut pacman, mut transform) in pacman_query.iter_mut() {
+        pacman.respawn(node_query);
+        transform.translation = pacman.node_position.extend(transform.translation.z);
+    }
+    for (mut ghost, mut transform) in ghost_query.iter_mut() {
+        ghost.respawn();
+        if let Ok(node) = node_query.get(ghost.current_node) {
+            transform.translation = node.position.extend(transform.translation.z);
+        }
+    }
+    *schedule = GhostModeSchedule::new();
+}
+
+// Pac-Man was caught: stop play for the death jingle
+pub fn pacman_death_system(
+    mut death_events: EventReader<PacmanDeathEvent>,
+    mut state_timer: ResMut<StateTimer>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // Several ghosts can touch Pac-Man on the same tick, he only dies once
+    if death_events.read().count() == 0 {
+        return;
+    }
+    info!("Pac-Man died");
+
+    *state_timer = StateTimer::new(DYING_SECONDS);
+    next_state.set(GameState::Dying);
+}
+
+// Count down the Ready pause, then play
+pub fn ready_state_system(
+    time: Res<Time>,
+    mut state_timer: ResMut<StateTimer>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if state_timer.0.tick(time.delta()).finished() {
+        next_state.set(GameState::Playing);
+    }
+}
+
+// Once the death jingle is over, take a life and restart the round or end the game
+#[allow(clippy::too_many_arguments)]
+pub fn dying_state_system(
+    time: Res<Time>,
+    mut state_timer: ResMut<StateTimer>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut stats: ResMut<GameStats>,
+    mut schedule: ResMut<GhostModeSchedule>,
+    high_scores: Res<HighScoreTable>,
+    players: Res<Players>,
+    mut swap_events: EventWriter<PlayerSwapEvent>,
+    mut pacman_query: Query<(&mut Pacman, &mut Transform), Without<Ghost>>,
+    mut ghost_query: Query<(&mut Ghost, &mut Transform), Without<Pacman>>,
+    node_query: Query<&MapNode>,
+) {
+    if !state_timer.0.tick(time.delta()).finished() {
+        return;
+    }
+    stats.lives = stats.lives.saturating_sub(1);
+    if players.other_can_play() {
+        // Two players take turns, every death hands the maze over
+        swap_events.send(PlayerSwapEvent);
+    } else if stats.lives == 0 {
+        // With two players the better score is the one that goes on the table
+        let other_score = players.waiting.as_ref().map_or(0, |other| other.stats.score);
+        if other_score > stats.score {
+            swap_events.send(PlayerSwapEvent);
+        }
+        let score = stats.score.max(other_score);
+        info!("Game over with {} points", score);
+        if high_scores.qualifies(score) {
+            next_state.set(GameState::NameEntry);
+        } else {
+            next_state.set(GameState::GameOver);
+        }
+        return;
     }
-    
-}
\ No newline at end of file
+
+    reset_actors(&mut schedule, &mut pacman_query, &mut ghost_query, &node_query);
+    *state_timer = StateTimer::new(READY_SECONDS);
+    next_state.set(GameState::Ready);
+}
+
+// Leaving the game over screen: fresh stats, full maze and the intro again
+#[allow(clippy::too_many_arguments)]
+pub fn new_game_system(
+    mut commands: Commands,
+    mut stats: ResMut<GameStats>,
+    mut pellet_group: ResMut<PelletGroup>,
+    mut fruit_tracker: ResMut<FruitTracker>,
+    mut schedule: ResMut<GhostModeSchedule>,
+    mut house: ResMut<GhostHouse>,
+    mut state_timer: ResMut<StateTimer>,
+    mut players: ResMut<Players>,
+    maze: Res<Maze>,
+    theme: Res<Theme>,
+    fruit_query: Query<Entity, With<Fruit>>,
+    mut pacman_query: Query<(&mut Pacman, &mut Transform), Without<Ghost>>,
+    mut ghost_query: Query<(&mut Ghost, &mut Transform), Without<Pacman>>,
+    node_query: Query<&MapNode>,
+) {
+    *stats = GameStats::new();
+    let count = players.count;
+    players.start(count, &maze);
+    pellet_group.setup_pellets(&mut commands, &maze, &theme);
+    stats.pellets_remaining = pellet_group.remaining();
+
+    for fruit in fruit_query.iter() {
+        commands.entity(fruit).despawn();
+    }
+    *fruit_tracker = FruitTracker::new();
+    *house = GhostHouse::new(1);
+
+    reset_actors(&mut schedule, &mut pacman_query, &mut ghost_query, &node_query);
+    *state_timer = StateTimer::new(INTRO_SECONDS);
+}
+
+// All pellets eaten: refill the maze and start the next level
+#[allow(clippy::too_many_arguments)]
+pub fn level_clear_system(
+    mut commands: Commands,
+    mut stats: ResMut<GameStats>,
+    mut pellet_group: ResMut<PelletGroup>,
+    mut fruit_tracker: ResMut<FruitTracker>,
+    mut schedule: ResMut<GhostModeSchedule>,
+    mut cleared_events: EventWriter<LevelClearedEvent>,
+    mut state_timer: ResMut<StateTimer>,
+    mut next_state: ResMut<NextState<GameState>>,
+    maze: Res<Maze>,
+    theme: Res<Theme>,
+    fruit_query: Query<Entity, With<Fruit>>,
+    mut pacman_query: Query<(&mut Pacman, &mut Transform), Without<Ghost>>,
+    mut ghost_query: Query<(&mut Ghost, &mut Transform), Without<Pacman>>,
+    node_query: Query<&MapNode>,
+) {
+    // A maze without pellets can never be cleared
+    if stats.pellets_remaining > 0 || pellet_group.remaining() > 0 || maze.pellets.is_empty() {
+        return;
+    }
+    info!("Level {} cleared", stats.level);
+    cleared_events.send(LevelClearedEvent { level: stats.level });
+
+    stats.level += 1;
+    stats.pellets_eaten = 0;
+    pellet_group.setup_pellets(&mut commands, &maze, &theme);
+    stats.pellets_remaining = pellet_group.remaining();
+
+    for fruit in fruit_query.iter() {
+        commands.entity(fruit).despawn();
+    }
+    fruit_tracker.start_level(stats.level);
+
+    reset_actors(&mut schedule, &mut pacman_query, &mut ghost_query, &node_query);
+    *state_timer = StateTimer::new(READY_SECONDS);
+    next_state.set(GameState::Ready);
+}
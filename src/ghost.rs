@@ -0,0 +1,505 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use rand::seq::SliceRandom;
+
+// The four ghosts, each with its own scatter corner and chase targeting
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum GhostName {
+    Blinky,
+    Pinky,
+    Inky,
+    Clyde,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum GhostMode {
+    Scatter,
+    Chase,
+    Frightened,
+    Eaten,        // Eyes racing back to the ghost house
+    LeavingHouse, // Heading out through the door after spawning or reviving
+    InHouse,      // Waiting in the ghost house to be released
+}
+
+#[derive(Component)]
+pub struct Ghost {
+    pub name: GhostName,
+    pub mode: GhostMode,
+    pub speed: f32,
+    pub node_direction: PacManDirection,
+    pub current_node: Entity,        // Node last left, or stood on
+    pub target_node: Option<Entity>, // Far end of the edge being walked, None while on a node
+    pub edge_progress: f32,          // Pixels travelled from current_node towards target_node
+    pub edge_length: f32,            // Pixels from current_node to target_node
+    pub frightened_timer: Timer,
+    pub start_node: Entity,
+    pub start_mode: GhostMode,
+    pub elroy_stage: usize, // Cruise Elroy: 0 normal, 1 and 2 are Blinky's speed ups
+}
+
+// Constants
+const GHOST_SPEED: f32 = 95.0 * (TILE_SIZE / 16.0);
+const FRIGHTENED_SPEED_SCALE: f32 = 0.5;
+const EATEN_SPEED_SCALE: f32 = 2.0;
+const FRIGHTENED_SECONDS: f32 = 6.0;
+const TUNNEL_SPEED_SCALE: f32 = 0.5;
+const ELROY_SPEED_SCALES: [f32; 2] = [1.07, 1.13];
+
+// Scatter/Chase alternation in seconds, chase forever after the last phase
+const MODE_PHASES: [f32; 7] = [7.0, 20.0, 7.0, 20.0, 5.0, 20.0, 5.0];
+
+// Implementation
+impl Ghost {
+    pub fn new(name: GhostName, current_node: Entity, mode: GhostMode) -> Self {
+        Ghost {
+            name,
+            mode,
+            speed: GHOST_SPEED,
+            node_direction: PacManDirection::Stop,
+            current_node,
+            target_node: None,
+            edge_progress: 0.0,
+            edge_length: 0.0,
+            frightened_timer: Timer::from_seconds(FRIGHTENED_SECONDS, TimerMode::Once),
+            start_node: current_node,
+            start_mode: mode,
+            elroy_stage: 0,
+        }
+    }
+
+    // Put the ghost back where it spawned, e.g. after Pac-Man dies
+    pub fn respawn(&mut self) {
+        self.current_node = self.start_node;
+        self.target_node = None;
+        self.edge_progress = 0.0;
+        self.edge_length = 0.0;
+        self.mode = self.start_mode;
+        self.node_direction = PacManDirection::Stop;
+    }
+
+    // Each ghost heads for its own corner of the maze while scattering
+    pub fn scatter_target(&self) -> Vec2 {
+        let (x, y) = (SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0);
+        match self.name {
+            GhostName::Blinky => Vec2::new(x, y),
+            GhostName::Pinky => Vec2::new(-x, y),
+            GhostName::Inky => Vec2::new(x, -y),
+            GhostName::Clyde => Vec2::new(-x, -y),
+        }
+    }
+
+    // Ghosts crawl through tunnels, except for eyes racing home
+    pub fn current_speed(&self, in_tunnel: bool) -> f32 {
+        match self.mode {
+            GhostMode::Eaten => self.speed * EATEN_SPEED_SCALE,
+            _ if in_tunnel => self.speed * TUNNEL_SPEED_SCALE,
+            GhostMode::Frightened => self.speed * FRIGHTENED_SPEED_SCALE,
+            _ => match self.elroy_stage {
+                0 => self.speed,
+                stage => self.speed * ELROY_SPEED_SCALES[stage.min(ELROY_SPEED_SCALES.len()) - 1],
+            },
+        }
+    }
+
+    // The mode the schedule asks for; Blinky in Cruise Elroy never scatters
+    pub fn scheduled_mode(&self, schedule: &GhostModeSchedule) -> GhostMode {
+        if self.elroy_stage > 0 {
+            GhostMode::Chase
+        } else {
+            schedule.current_mode()
+        }
+    }
+
+    // Only eyes and ghosts on their way out may use the house door
+    pub fn actor_kind(&self) -> ActorKind {
+        match self.mode {
+            GhostMode::Eaten => ActorKind::GhostEyes,
+            GhostMode::LeavingHouse => ActorKind::LeavingGhost,
+            _ => ActorKind::Ghost,
+        }
+    }
+
+    // Power pellet eaten: turn blue and reverse
+    pub fn frighten(&mut self) {
+        match self.mode {
+            GhostMode::Scatter | GhostMode::Chase => {
+                self.mode = GhostMode::Frightened;
+                self.reverse();
+            }
+            GhostMode::Frightened => {}
+            _ => return,
+        }
+        self.frightened_timer.reset();
+    }
+
+    // Caught by Pac-Man while frightened: become eyes and go home
+    pub fn set_eaten(&mut self) {
+        if self.mode == GhostMode::Frightened {
+            self.mode = GhostMode::Eaten;
+        }
+    }
+
+    // Turn around mid-edge by swapping the nodes we are travelling between
+    // Scattering, chasing and frightened ghosts are never on the one-way door edges
+    pub fn reverse(&mut self) {
+        if let Some(target) = self.target_node {
+            self.target_node = Some(self.current_node);
+            self.current_node = target;
+            self.node_direction = self.node_direction.opposite();
+            self.edge_progress = (self.edge_length - self.edge_progress).max(0.0);
+        }
+    }
+
+    // Leave a node along the edge in a direction, or stand still if this ghost can't go that way
+    pub fn start_edge(&mut self, direction: PacManDirection, node: &MapNode) -> bool {
+        let Some(edge) = node.edge(direction).filter(|edge| edge.allows(self.actor_kind())) else {
+            self.node_direction = PacManDirection::Stop;
+            self.target_node = None;
+            return false;
+        };
+        self.node_direction = direction;
+        self.target_node = Some(edge.to);
+        self.edge_length = edge.pixel_length();
+        self.edge_progress = 0.0;
+        true
+    }
+
+    // Reached the end of the edge
+    pub fn arrive(&mut self) {
+        if let Some(target) = self.target_node.take() {
+            self.current_node = target;
+        }
+        self.edge_progress = 0.0;
+        self.edge_length = 0.0;
+    }
+
+    // Pick the direction to take from the current node towards a goal position
+    // Ghosts never reverse unless it is the only option
+    pub fn choose_direction(&self, node: &MapNode, goal: Vec2, node_query: &Query<&MapNode>) -> PacManDirection {
+        let candidates = self.candidate_directions(node);
+        candidates
+            .into_iter()
+            .filter_map(|direction| {
+                let neighbor = node.neighbor(direction, self.actor_kind())?;
+                let position = node_query.get(neighbor).ok()?.position;
+                Some((direction, position.distance_squared(goal)))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(direction, _)| direction)
+            .unwrap_or(PacManDirection::Stop)
+    }
+
+    // Frightened ghosts wander randomly at each node
+    pub fn random_direction(&self, node: &MapNode, rng: &mut GameRng) -> PacManDirection {
+        let candidates = self.candidate_directions(node);
+        candidates
+            .choose(rng)
+            .copied()
+            .unwrap_or(PacManDirection::Stop)
+    }
+
+    fn candidate_directions(&self, node: &MapNode) -> Vec<PacManDirection> {
+        let open: Vec<PacManDirection> = PacManDirection::ALL
+            .into_iter()
+            .filter(|direction| node.neighbor(*direction, self.actor_kind()).is_some())
+            .collect();
+        let forward: Vec<PacManDirection> = open
+            .iter()
+            .copied()
+            .filter(|direction| *direction != self.node_direction.opposite())
+            .collect();
+        if forward.is_empty() { open } else { forward }
+    }
+
+    // The player's pick if it is open and not a reversal, otherwise straight on, otherwise any way forward
+    pub fn steered_direction(&self, node: &MapNode, wanted: PacManDirection) -> PacManDirection {
+        let candidates = self.candidate_directions(node);
+        if candidates.contains(&wanted) {
+            wanted
+        } else if candidates.contains(&self.node_direction) {
+            self.node_direction
+        } else {
+            candidates.first().copied().unwrap_or(PacManDirection::Stop)
+        }
+    }
+
+    // Where the ghost is aiming while chasing, per the arcade rules
+    pub fn chase_target(&self, position: Vec2, pacman_position: Vec2, pacman_direction: PacManDirection, blinky_position: Vec2) -> Vec2 {
+        let facing = pacman_direction.to_vec2() * TILE_SIZE;
+        match self.name {
+            GhostName::Blinky => pacman_position,
+            GhostName::Pinky => pacman_position + facing * 4.0,
+            GhostName::Inky => {
+                let pivot = pacman_position + facing * 2.0;
+                pivot * 2.0 - blinky_position
+            }
+            GhostName::Clyde => {
+                if position.distance(pacman_position) > TILE_SIZE * 8.0 {
+                    pacman_position
+                } else {
+                    self.scatter_target()
+                }
+            }
+        }
+    }
+
+    // Spawn the four ghosts in and around the ghost house
+    pub fn spawn_ghosts(
+        mut commands: Commands,
+        node_group: Res<NodeGroup>,
+        theme: Res<Theme>,
+        node_query: Query<&MapNode>,
+    ) {
+        let (Some(home), Some(exit)) = (node_group.home_node, node_group.house_exit) else {
+            eprintln!("Maze has no ghost house, not spawning ghosts");
+            return;
+        };
+        let home_node = node_query.get(home).ok();
+        let left = home_node.and_then(|node| node.neighbor(PacManDirection::Left, ActorKind::Ghost)).unwrap_or(home);
+        let right = home_node.and_then(|node| node.neighbor(PacManDirection::Right, ActorKind::Ghost)).unwrap_or(home);
+
+        let ghosts = [
+            (GhostName::Blinky, exit, GhostMode::Scatter),
+            (GhostName::Pinky, home, GhostMode::InHouse),
+            (GhostName::Inky, left, GhostMode::InHouse),
+            (GhostName::Clyde, right, GhostMode::InHouse),
+        ];
+
+        for (name, node_entity, mode) in ghosts {
+            let Ok(node) = node_query.get(node_entity) else {
+                continue;
+            };
+            let ghost = Ghost::new(name, node_entity, mode);
+            let color = theme.ghost(name);
+            commands.spawn((
+                ghost,
+                TileOccupancy::new(node.position),
+                SkipTicks::default(),
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::splat(TILE_SIZE * 0.8)),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(Vec3::new(node.position.x, node.position.y, 1.0)),
+                    ..Default::default()
+                },
+            ));
+        }
+    }
+}
+
+// Global Scatter/Chase timer shared by every ghost
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct GhostModeSchedule {
+    timer: Timer,
+    phase: usize,
+}
+
+impl GhostModeSchedule {
+    pub fn new() -> Self {
+        GhostModeSchedule {
+            timer: Timer::from_seconds(MODE_PHASES[0], TimerMode::Once),
+            phase: 0,
+        }
+    }
+
+    // Even phases scatter, odd phases chase
+    pub fn current_mode(&self) -> GhostMode {
+        if self.phase.is_multiple_of(2) {
+            GhostMode::Scatter
+        } else {
+            GhostMode::Chase
+        }
+    }
+}
+
+impl Default for GhostModeSchedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Advance the Scatter/Chase schedule and expire frightened mode
+pub fn ghost_mode_system(
+    time: Res<Time>,
+    mut schedule: ResMut<GhostModeSchedule>,
+    mut ghost_query: Query<&mut Ghost>,
+) {
+    let mut phase_changed = false;
+    if schedule.phase < MODE_PHASES.len() {
+        schedule.timer.tick(time.delta());
+        if schedule.timer.finished() {
+            schedule.phase += 1;
+            if let Some(&seconds) = MODE_PHASES.get(schedule.phase) {
+                schedule.timer = Timer::from_seconds(seconds, TimerMode::Once);
+            }
+            phase_changed = true;
+        }
+    }
+    for mut ghost in ghost_query.iter_mut() {
+        match ghost.mode {
+            GhostMode::Scatter | GhostMode::Chase if phase_changed => {
+                // Ghosts reverse whenever the schedule flips
+                ghost.mode = ghost.scheduled_mode(&schedule);
+                ghost.reverse();
+            }
+            GhostMode::Frightened => {
+                ghost.frightened_timer.tick(time.delta());
+                if ghost.frightened_timer.finished() {
+                    ghost.mode = ghost.scheduled_mode(&schedule);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Ghost Node Movement System
+#[allow(clippy::too_many_arguments)]
+pub fn ghost_movement_system(
+    time: Res<Time>,
+    schedule: Res<GhostModeSchedule>,
+    node_group: Res<NodeGroup>,
+    mut rng: ResMut<GameRng>,
+    ghost_player: Res<GhostPlayer>,
+    mut ghost_query: Query<(&mut Ghost, &mut Transform, &mut SkipTicks)>,
+    pacman_query: Query<(&Pacman, &Transform), Without<Ghost>>,
+    node_query: Query<&MapNode>,
+) {
+    let (pacman_position, pacman_direction) = pacman_query
+        .iter()
+        .next()
+        .map(|(pacman, transform)| (transform.translation.truncate(), pacman.facing))
+        .unwrap_or((Vec2::ZERO, PacManDirection::Stop));
+    let blinky_position = ghost_query
+        .iter()
+        .find(|(ghost, _, _)| ghost.name == GhostName::Blinky)
+        .map(|(_, transform, _)| transform.translation.truncate())
+        .unwrap_or(Vec2::ZERO);
+
+    for (mut ghost, mut transform, mut skip_ticks) in ghost_query.iter_mut() {
+        let in_tunnel = node_query
+            .get(ghost.current_node)
+            .ok()
+            .and_then(|node| node.edge(ghost.node_direction))
+            .is_some_and(|edge| edge.tunnel);
+        let mut remaining = ghost.current_speed(in_tunnel) * skip_ticks.consume(time.delta_seconds());
+
+        while remaining > 0.0 {
+            // Only decide at a node: either we have arrived or we have nowhere to go yet
+            if ghost.target_node.is_none() {
+                let Ok(node) = node_query.get(ghost.current_node) else {
+                    break;
+                };
+
+                // Eyes revive once home, then leave; leaving ghosts rejoin the schedule at the exit
+                if ghost.mode == GhostMode::Eaten && Some(ghost.current_node) == node_group.home_node {
+                    ghost.mode = GhostMode::LeavingHouse;
+                }
+                if ghost.mode == GhostMode::LeavingHouse && Some(ghost.current_node) == node_group.house_exit {
+                    ghost.mode = ghost.scheduled_mode(&schedule);
+                }
+
+                let direction = match ghost.mode {
+                    GhostMode::Eaten | GhostMode::LeavingHouse => {
+                        let goal = if ghost.mode == GhostMode::Eaten {
+                            node_group.home_node
+                        } else {
+                            node_group.house_exit
+                        };
+                        goal.and_then(|goal| direction_towards(ghost.current_node, goal, &node_query, ghost.actor_kind()))
+                            .unwrap_or(PacManDirection::Stop)
+                    }
+                    GhostMode::InHouse => PacManDirection::Stop,
+                    _ if ghost_player.steers(&ghost) => ghost.steered_direction(node, ghost_player.wanted),
+                    GhostMode::Frightened => ghost.random_direction(node, &mut rng),
+                    GhostMode::Scatter => ghost.choose_direction(node, ghost.scatter_target(), &node_query),
+                    GhostMode::Chase => {
+                        let goal = ghost.chase_target(node.position, pacman_position, pacman_direction, blinky_position);
+                        ghost.choose_direction(node, goal, &node_query)
+                    }
+                };
+                if !ghost.start_edge(direction, node) {
+                    break;
+                }
+            }
+
+            // Move towards the target node, carrying any leftover distance past it
+            let to_go = ghost.edge_length - ghost.edge_progress;
+            if remaining < to_go {
+                ghost.edge_progress += remaining;
+                break;
+            }
+            remaining -= to_go;
+            ghost.arrive();
+        }
+
+        if let Ok(node) = node_query.get(ghost.current_node) {
+            let position = node.position + ghost.node_direction.to_vec2() * ghost.edge_progress;
+            transform.translation = position.extend(transform.translation.z);
+        }
+    }
+}
+
+// Blue when frightened, small white eyes when eaten
+pub fn ghost_appearance_system(theme: Res<Theme>, mut ghost_query: Query<(&Ghost, &mut Sprite)>) {
+    for (ghost, mut sprite) in ghost_query.iter_mut() {
+        let (color, size) = match ghost.mode {
+            GhostMode::Frightened => (theme.ghosts.frightened, TILE_SIZE * 0.8),
+            GhostMode::Eaten => (theme.ghosts.eyes, TILE_SIZE * 0.4),
+            _ => (theme.ghost(ghost.name), TILE_SIZE * 0.8),
+        };
+        sprite.color = color;
+        sprite.custom_size = Some(Vec2::splat(size));
+    }
+}
+
+// Eaten ghosts turn into eyes
+pub fn ghost_eaten_system(
+    mut eaten_events: EventReader<GhostEatenEvent>,
+    mut ghost_query: Query<&mut Ghost>,
+) {
+    for event in eaten_events.read() {
+        if let Ok(mut ghost) = ghost_query.get_mut(event.ghost) {
+            ghost.set_eaten();
+            info!("{:?} eaten", event.name);
+        }
+    }
+}
+
+// Power pellets frighten every ghost that is out chasing or scattering
+pub fn power_pellet_system(
+    mut pellet_events: EventReader<PelletEatenEvent>,
+    mut ghost_query: Query<&mut Ghost>,
+) {
+    for event in pellet_events.read() {
+        if event.kind != PelletKind::PowerPellet {
+            continue;
+        }
+        for mut ghost in ghost_query.iter_mut() {
+            ghost.frighten();
+        }
+    }
+}
+
+// Blinky speeds up in two stages as the maze empties, and stops scattering while he does
+// After a death he is back to normal until Clyde leaves the house, as in the arcade
+pub fn cruise_elroy_system(stats: Res<GameStats>, house: Res<GhostHouse>, mut ghost_query: Query<&mut Ghost>) {
+    let thresholds = level_spec(stats.level).elroy_pellets;
+    let stage = if house.elroy_suspended {
+        0
+    } else {
+        thresholds.iter().filter(|&&threshold| stats.pellets_remaining <= threshold).count()
+    };
+    for mut ghost in ghost_query.iter_mut() {
+        if ghost.name != GhostName::Blinky || ghost.elroy_stage == stage {
+            continue;
+        }
+        ghost.elroy_stage = stage;
+        if stage > 0 && ghost.mode == GhostMode::Scatter {
+            ghost.mode = GhostMode::Chase;
+        }
+    }
+}
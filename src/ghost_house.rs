@@ -0,0 +1,241 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Decides when the ghosts waiting in the house may leave, per the arcade rules:
+// the next ghost in line counts pellets until its level limit, after a death a shared
+// counter is used instead, and going too long without eating sends the next ghost out
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct GhostHouse {
+    pub dot_counters: HashMap<GhostName, usize>,
+    pub global_counter: Option<usize>, // Only counting after a life has been lost
+    pub idle_timer: Timer,
+    #[serde(default)]
+    pub elroy_suspended: bool, // Blinky stays at normal speed after a death until Clyde is let out
+    dot_limits: [usize; 3],
+}
+
+// Constants
+const RELEASE_ORDER: [GhostName; 3] = [GhostName::Pinky, GhostName::Inky, GhostName::Clyde];
+const GLOBAL_DOT_LIMITS: [usize; 3] = [7, 17, 32];
+
+impl GhostHouse {
+    pub fn new(level: u32) -> Self {
+        let spec = level_spec(level);
+        GhostHouse {
+            dot_counters: RELEASE_ORDER.into_iter().map(|name| (name, 0)).collect(),
+            global_counter: None,
+            idle_timer: Timer::from_seconds(spec.house_idle_seconds, TimerMode::Once),
+            elroy_suspended: false,
+            dot_limits: spec.house_dot_limits,
+        }
+    }
+
+    // The personal counters keep their values but the shared counter takes over
+    pub fn life_lost(&mut self) {
+        self.global_counter = Some(0);
+        self.elroy_suspended = true;
+        self.idle_timer.reset();
+    }
+
+    fn count_pellet(&mut self, waiting: Option<GhostName>) {
+        self.idle_timer.reset();
+        if let Some(count) = self.global_counter.as_mut() {
+            *count += 1;
+        } else if let Some(name) = waiting {
+            *self.dot_counters.entry(name).or_insert(0) += 1;
+        }
+    }
+
+    // Whether the next ghost in line has waited for enough pellets
+    fn dots_reached(&mut self, name: GhostName) -> bool {
+        let index = RELEASE_ORDER.iter().position(|&other| other == name).unwrap_or(0);
+        match self.global_counter {
+            // Clyde still inside when the shared count runs out: back to the personal counters
+            Some(count) if name == GhostName::Clyde && count >= GLOBAL_DOT_LIMITS[index] => {
+                self.global_counter = None;
+                false
+            }
+            Some(count) => count >= GLOBAL_DOT_LIMITS[index],
+            None => self.dot_counters.get(&name).copied().unwrap_or(0) >= self.dot_limits[index],
+        }
+    }
+}
+
+// Count pellets for the ghost next in line and let it out when its time comes
+pub fn ghost_release_system(
+    time: Res<Time>,
+    stats: Res<GameStats>,
+    mut house: ResMut<GhostHouse>,
+    mut pellet_events: EventReader<PelletEatenEvent>,
+    mut death_events: EventReader<PacmanDeathEvent>,
+    mut cleared_events: EventReader<LevelClearedEvent>,
+    mut ghost_query: Query<&mut Ghost>,
+) {
+    if cleared_events.read().count() > 0 {
+        *house = GhostHouse::new(stats.level);
+        pellet_events.clear();
+        return;
+    }
+    if death_events.read().count() > 0 {
+        house.life_lost();
+        pellet_events.clear();
+        return;
+    }
+
+    let waiting = RELEASE_ORDER.into_iter().find(|&name| {
+        ghost_query.iter().any(|ghost| ghost.name == name && ghost.mode == GhostMode::InHouse)
+    });
+    for _ in pellet_events.read() {
+        house.count_pellet(waiting);
+    }
+    let Some(name) = waiting else {
+        return;
+    };
+
+    let timed_out = house.idle_timer.tick(time.delta()).finished();
+    if !house.dots_reached(name) && !timed_out {
+        return;
+    }
+    house.idle_timer.reset();
+    if name == GhostName::Clyde {
+        house.elroy_suspended = false;
+    }
+    for mut ghost in ghost_query.iter_mut() {
+        if ghost.name == name && ghost.mode == GhostMode::InHouse {
+            ghost.mode = GhostMode::LeavingHouse;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // Constants
+    const TICK_SECONDS: f32 = 0.01;
+
+    // The release system on its own, with Pinky, Inky and Clyde waiting in the house
+    fn build_world(level: u32) -> (World, Schedule) {
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+        world.insert_resource(GameStats::new());
+        world.insert_resource(GhostHouse::new(level));
+        world.init_resource::<Events<PelletEatenEvent>>();
+        world.init_resource::<Events<PacmanDeathEvent>>();
+        world.init_resource::<Events<LevelClearedEvent>>();
+        for name in RELEASE_ORDER {
+            world.spawn(Ghost::new(name, Entity::PLACEHOLDER, GhostMode::InHouse));
+        }
+        let mut schedule = Schedule::default();
+        schedule.add_systems(ghost_release_system);
+        (world, schedule)
+    }
+
+    fn tick(world: &mut World, schedule: &mut Schedule, seconds: f32, pellets: usize) {
+        for _ in 0..pellets {
+            world.send_event(PelletEatenEvent { kind: PelletKind::Pellet, tile: (0, 0) });
+        }
+        world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(seconds));
+        schedule.run(world);
+    }
+
+    // Ghosts let out so far, in release order
+    fn released(world: &mut World) -> Vec<GhostName> {
+        let mut ghosts = world.query::<&Ghost>();
+        RELEASE_ORDER
+            .into_iter()
+            .filter(|&name| ghosts.iter(world).any(|ghost| ghost.name == name && ghost.mode == GhostMode::LeavingHouse))
+            .collect()
+    }
+
+    // Eat one pellet a tick until the ghost is out, and say how many it took
+    fn pellets_until_released(world: &mut World, schedule: &mut Schedule, name: GhostName) -> usize {
+        for eaten in 1..=100 {
+            tick(world, schedule, TICK_SECONDS, 1);
+            if released(world).contains(&name) {
+                return eaten;
+            }
+        }
+        panic!("{:?} never left the house", name);
+    }
+
+    #[test]
+    fn personal_counters_release_in_order() {
+        // Level 1: Pinky leaves at once, Inky after 30 pellets, Clyde after 60 more
+        let (mut world, mut schedule) = build_world(1);
+        tick(&mut world, &mut schedule, TICK_SECONDS, 0);
+        assert_eq!(released(&mut world), vec![GhostName::Pinky]);
+        assert_eq!(pellets_until_released(&mut world, &mut schedule, GhostName::Inky), 30);
+        assert_eq!(released(&mut world), vec![GhostName::Pinky, GhostName::Inky]);
+        assert_eq!(pellets_until_released(&mut world, &mut schedule, GhostName::Clyde), 60);
+    }
+
+    #[test]
+    fn idle_timer_sends_the_next_ghost_out() {
+        let (mut world, mut schedule) = build_world(1);
+        tick(&mut world, &mut schedule, TICK_SECONDS, 0);
+        tick(&mut world, &mut schedule, 3.9, 0);
+        assert_eq!(released(&mut world), vec![GhostName::Pinky]);
+        tick(&mut world, &mut schedule, 0.2, 0);
+        assert_eq!(released(&mut world), vec![GhostName::Pinky, GhostName::Inky]);
+
+        // Eating a pellet starts the wait over
+        tick(&mut world, &mut schedule, 3.9, 0);
+        tick(&mut world, &mut schedule, TICK_SECONDS, 1);
+        tick(&mut world, &mut schedule, 3.9, 0);
+        assert_eq!(released(&mut world), vec![GhostName::Pinky, GhostName::Inky]);
+        tick(&mut world, &mut schedule, 0.2, 0);
+        assert_eq!(released(&mut world), RELEASE_ORDER.to_vec());
+    }
+
+    #[test]
+    fn global_counter_after_a_life_is_lost() {
+        let (mut world, mut schedule) = build_world(1);
+        tick(&mut world, &mut schedule, TICK_SECONDS, 0);
+        for _ in 0..10 {
+            tick(&mut world, &mut schedule, TICK_SECONDS, 1);
+        }
+        assert_eq!(world.resource::<GhostHouse>().dot_counters[&GhostName::Inky], 10);
+
+        // Pac-Man dies and everyone goes back to the start
+        world.send_event(PacmanDeathEvent { ghost: Entity::PLACEHOLDER });
+        tick(&mut world, &mut schedule, TICK_SECONDS, 0);
+        for mut ghost in world.query::<&mut Ghost>().iter_mut(&mut world) {
+            ghost.respawn();
+        }
+        let house = world.resource::<GhostHouse>();
+        assert_eq!(house.global_counter, Some(0));
+        assert_eq!(house.dot_counters[&GhostName::Inky], 10, "personal counters keep their values");
+
+        // The shared counter lets Pinky out at 7 and Inky at 17, in that order
+        assert_eq!(pellets_until_released(&mut world, &mut schedule, GhostName::Pinky), 7);
+        assert_eq!(released(&mut world), vec![GhostName::Pinky]);
+        assert_eq!(pellets_until_released(&mut world, &mut schedule, GhostName::Inky), 10);
+        assert_eq!(released(&mut world), vec![GhostName::Pinky, GhostName::Inky]);
+        assert_eq!(world.resource::<GhostHouse>().dot_counters[&GhostName::Inky], 10);
+
+        // Clyde doesn't leave on the shared count: at 32 it switches off and his own counter takes over
+        for _ in 17..32 {
+            tick(&mut world, &mut schedule, TICK_SECONDS, 1);
+        }
+        assert_eq!(world.resource::<GhostHouse>().global_counter, None);
+        assert_eq!(released(&mut world), vec![GhostName::Pinky, GhostName::Inky]);
+        assert_eq!(pellets_until_released(&mut world, &mut schedule, GhostName::Clyde), 60);
+    }
+
+    #[test]
+    fn elroy_waits_for_clyde_after_a_death() {
+        let (mut world, mut schedule) = build_world(1);
+        world.send_event(PacmanDeathEvent { ghost: Entity::PLACEHOLDER });
+        tick(&mut world, &mut schedule, TICK_SECONDS, 0);
+        assert!(world.resource::<GhostHouse>().elroy_suspended);
+
+        pellets_until_released(&mut world, &mut schedule, GhostName::Inky);
+        assert!(world.resource::<GhostHouse>().elroy_suspended);
+        tick(&mut world, &mut schedule, 4.1, 0);
+        assert_eq!(released(&mut world), RELEASE_ORDER.to_vec());
+        assert!(!world.resource::<GhostHouse>().elroy_suspended);
+    }
+}
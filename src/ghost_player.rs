@@ -0,0 +1,83 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+
+// A second player steering one of the ghosts with WASD or a gamepad
+// They pick the turn taken at each node, but like any ghost can't reverse on their own
+#[derive(Resource)]
+pub struct GhostPlayer {
+    pub ghost: Option<GhostName>, // None when every ghost is on autopilot
+    pub wanted: PacManDirection,  // Last direction pressed, tried at every node
+}
+
+// Constants
+const STICK_DEAD_ZONE: f32 = 0.5;
+
+impl GhostPlayer {
+    pub fn new() -> Self {
+        GhostPlayer {
+            ghost: None,
+            wanted: PacManDirection::Stop,
+        }
+    }
+
+    // Only roaming ghosts are steered; eyes and the ghost house still run themselves
+    pub fn steers(&self, ghost: &Ghost) -> bool {
+        self.ghost == Some(ghost.name)
+            && matches!(ghost.mode, GhostMode::Scatter | GhostMode::Chase | GhostMode::Frightened)
+    }
+}
+
+impl Default for GhostPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// WASD, or the d-pad or left stick of any gamepad
+pub fn ghost_player_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut ghost_player: ResMut<GhostPlayer>,
+) {
+    if ghost_player.ghost.is_none() {
+        return;
+    }
+
+    let mut direction = if keyboard_input.pressed(KeyCode::KeyW) {
+        PacManDirection::Up
+    } else if keyboard_input.pressed(KeyCode::KeyS) {
+        PacManDirection::Down
+    } else if keyboard_input.pressed(KeyCode::KeyA) {
+        PacManDirection::Left
+    } else if keyboard_input.pressed(KeyCode::KeyD) {
+        PacManDirection::Right
+    } else {
+        PacManDirection::Stop
+    };
+
+    for gamepad in gamepads.iter() {
+        if direction != PacManDirection::Stop {
+            break;
+        }
+        let pressed = |button| gamepad_buttons.pressed(GamepadButton::new(gamepad, button));
+        let axis = |axis| gamepad_axes.get(GamepadAxis::new(gamepad, axis)).unwrap_or(0.0);
+        let (x, y) = (axis(GamepadAxisType::LeftStickX), axis(GamepadAxisType::LeftStickY));
+        direction = if pressed(GamepadButtonType::DPadUp) || y > STICK_DEAD_ZONE {
+            PacManDirection::Up
+        } else if pressed(GamepadButtonType::DPadDown) || y < -STICK_DEAD_ZONE {
+            PacManDirection::Down
+        } else if pressed(GamepadButtonType::DPadLeft) || x < -STICK_DEAD_ZONE {
+            PacManDirection::Left
+        } else if pressed(GamepadButtonType::DPadRight) || x > STICK_DEAD_ZONE {
+            PacManDirection::Right
+        } else {
+            PacManDirection::Stop
+        };
+    }
+
+    if direction != PacManDirection::Stop {
+        ghost_player.wanted = direction;
+    }
+}
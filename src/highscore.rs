@@ -0,0 +1,427 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub initials: String,
+    pub score: u32,
+    pub level: u32,
+    pub date: String, // YYYY-MM-DD
+}
+
+// Top scores, best first, stored as JSON in the user's data directory
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    pub entries: Vec<HighScoreEntry>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+// Constants
+const MAX_ENTRIES: usize = 10;
+const INITIALS_LENGTH: usize = 3;
+const GAME_OVER_SECONDS: f32 = 10.0;
+const SCREEN_FONT_SIZE: f32 = 24.0;
+
+impl HighScoreTable {
+    // Default location, falling back to the working directory if there is no data dir
+    pub fn default_path() -> PathBuf {
+        dirs::data_dir()
+            .map(|dir| dir.join("pacman_bevy"))
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("highscores.json")
+    }
+
+    pub fn load() -> Self {
+        Self::load_from(Self::default_path())
+    }
+
+    // A missing file is an empty table; a corrupt one is moved aside so it isn't lost
+    pub fn load_from(path: PathBuf) -> Self {
+        let mut table = match fs::read_to_string(&path) {
+            Ok(contents) => match serde_json::from_str::<HighScoreTable>(&contents) {
+                Ok(table) => table,
+                Err(e) => {
+                    eprintln!("High score file {} is corrupt: {}", path.display(), e);
+                    let backup = path.with_extension("json.corrupt");
+                    if let Err(e) = fs::rename(&path, &backup) {
+                        eprintln!("Failed to move corrupt high score file aside: {}", e);
+                    }
+                    HighScoreTable::default()
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HighScoreTable::default(),
+            Err(e) => {
+                eprintln!("Failed to read high score file {}: {}", path.display(), e);
+                HighScoreTable::default()
+            }
+        };
+        table.path = Some(path);
+        table.sanitize();
+        table
+    }
+
+    // Write to a temporary file first so a crash can't leave a half-written table
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, contents)?;
+        fs::rename(&temp_path, path)
+    }
+
+    // Hand-edited files may be out of order, too long or have odd initials
+    fn sanitize(&mut self) {
+        for entry in self.entries.iter_mut() {
+            entry.initials = normalize_initials(&entry.initials);
+        }
+        self.entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        self.entries.truncate(MAX_ENTRIES);
+    }
+
+    pub fn best(&self) -> u32 {
+        self.entries.first().map_or(0, |entry| entry.score)
+    }
+
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0 && (self.entries.len() < MAX_ENTRIES || self.entries.iter().any(|entry| score > entry.score))
+    }
+
+    // Insert keeping the table sorted, returns the rank if the entry made it in
+    pub fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        let rank = self.entries.iter().position(|existing| entry.score > existing.score).unwrap_or(self.entries.len());
+        if rank >= MAX_ENTRIES {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        Some(rank)
+    }
+}
+
+// Three upper case letters, padded with 'A'
+fn normalize_initials(initials: &str) -> String {
+    let mut letters: String = initials
+        .chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| c.to_ascii_uppercase())
+        .take(INITIALS_LENGTH)
+        .collect();
+    while letters.len() < INITIALS_LENGTH {
+        letters.push('A');
+    }
+    letters
+}
+
+// Today's date without pulling in a date crate
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() / 86_400) as i64;
+    date_from_days(days)
+}
+
+// Days since 1970-01-01 to YYYY-MM-DD (civil-from-days)
+fn date_from_days(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Initials being typed on the name entry screen
+#[derive(Resource)]
+pub struct InitialsEntry {
+    pub letters: [char; INITIALS_LENGTH],
+    pub cursor: usize,
+}
+
+impl InitialsEntry {
+    pub fn new() -> Self {
+        InitialsEntry {
+            letters: ['A'; INITIALS_LENGTH],
+            cursor: 0,
+        }
+    }
+
+    // Cycle the letter under the cursor through A-Z
+    fn step_letter(&mut self, forward: bool) {
+        let offset = self.letters[self.cursor] as u8 - b'A';
+        let offset = if forward { (offset + 1) % 26 } else { (offset + 25) % 26 };
+        self.letters[self.cursor] = (b'A' + offset) as char;
+    }
+
+    fn display(&self) -> String {
+        self.letters
+            .iter()
+            .enumerate()
+            .map(|(i, letter)| if i == self.cursor { format!("[{}]", letter) } else { format!(" {} ", letter) })
+            .collect()
+    }
+}
+
+impl Default for InitialsEntry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Markers for the full screen overlays
+#[derive(Component)]
+pub struct NameEntryScreen;
+
+#[derive(Component)]
+pub struct InitialsText;
+
+#[derive(Component)]
+pub struct GameOverScreen;
+
+// Full screen column of centred text lines
+pub fn spawn_screen<T: Component>(commands: &mut Commands, marker: T, font: Handle<Font>, theme: &Theme, lines: Vec<String>) -> Entity {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(6.0),
+                    ..default()
+                },
+                background_color: theme.hud.screen.into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            marker,
+        ))
+        .with_children(|screen| {
+            for line in lines {
+                screen.spawn(TextBundle::from_section(
+                    line,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: SCREEN_FONT_SIZE,
+                        color: theme.hud.text,
+                    },
+                ));
+            }
+        })
+        .id()
+}
+
+pub fn despawn_screen<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn setup_name_entry(mut commands: Commands, asset_server: Res<AssetServer>, stats: Res<GameStats>, theme: Res<Theme>) {
+    let entry = InitialsEntry::new();
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let screen = spawn_screen(
+        &mut commands,
+        NameEntryScreen,
+        font.clone(),
+        &theme,
+        vec![
+            "NEW HIGH SCORE".to_string(),
+            stats.score.to_string(),
+            "ENTER YOUR INITIALS".to_string(),
+        ],
+    );
+    commands.entity(screen).with_children(|screen| {
+        screen.spawn((
+            TextBundle::from_section(
+                entry.display(),
+                TextStyle {
+                    font,
+                    font_size: SCREEN_FONT_SIZE * 1.5,
+                    color: theme.hud.highlight,
+                },
+            ),
+            InitialsText,
+        ));
+    });
+    commands.insert_resource(entry);
+}
+
+// Arrows pick letters, typing sets them directly, Enter saves
+pub fn name_entry_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut key_events: EventReader<KeyboardInput>,
+    mut entry: ResMut<InitialsEntry>,
+    mut high_scores: ResMut<HighScoreTable>,
+    mut next_state: ResMut<NextState<GameState>>,
+    stats: Res<GameStats>,
+    mut text_query: Query<&mut Text, With<InitialsText>>,
+) {
+    for event in key_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        if let Key::Character(typed) = &event.logical_key {
+            if let Some(letter) = typed.chars().next().filter(|c| c.is_ascii_alphabetic()) {
+                let cursor = entry.cursor;
+                entry.letters[cursor] = letter.to_ascii_uppercase();
+                entry.cursor = (cursor + 1).min(INITIALS_LENGTH - 1);
+            }
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        entry.step_letter(true);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        entry.step_letter(false);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        entry.cursor = entry.cursor.saturating_sub(1);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        entry.cursor = (entry.cursor + 1).min(INITIALS_LENGTH - 1);
+    }
+
+    if entry.is_changed() {
+        for mut text in text_query.iter_mut() {
+            text.sections[0].value = entry.display();
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        let rank = high_scores.insert(HighScoreEntry {
+            initials: entry.letters.iter().collect(),
+            score: stats.score,
+            level: stats.level,
+            date: today(),
+        });
+        if rank.is_some() {
+            if let Err(e) = high_scores.save() {
+                eprintln!("Failed to save high scores: {}", e);
+            }
+        }
+        next_state.set(GameState::GameOver);
+    }
+}
+
+pub fn setup_game_over(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    high_scores: Res<HighScoreTable>,
+    theme: Res<Theme>,
+    mut state_timer: ResMut<StateTimer>,
+) {
+    let mut lines = vec!["GAME OVER".to_string(), String::new(), "HIGH SCORES".to_string()];
+    for (rank, entry) in high_scores.entries.iter().enumerate() {
+        lines.push(format!(
+            "{:>2}. {}  {:>7}  L{:<2}  {}",
+            rank + 1,
+            entry.initials,
+            entry.score,
+            entry.level,
+            entry.date
+        ));
+    }
+    lines.push(String::new());
+    lines.push("PRESS ENTER".to_string());
+
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    spawn_screen(&mut commands, GameOverScreen, font, &theme, lines);
+    *state_timer = StateTimer::new(GAME_OVER_SECONDS);
+}
+
+// Leave the table up for a while or until Enter, then back to the title screen
+pub fn game_over_system(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut state_timer: ResMut<StateTimer>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if state_timer.0.tick(time.delta()).finished() || keyboard_input.just_pressed(KeyCode::Enter) {
+        next_state.set(GameState::Title);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory per test so runs don't see each other's files
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pacman_highscore_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entry(initials: &str, score: u32) -> HighScoreEntry {
+        HighScoreEntry { initials: initials.to_string(), score, level: 1, date: "2000-01-01".to_string() }
+    }
+
+    #[test]
+    fn missing_file_is_an_empty_table() {
+        let dir = scratch_dir("missing");
+        let path = dir.join("highscores.json");
+        let mut table = HighScoreTable::load_from(path.clone());
+        assert!(table.entries.is_empty());
+        assert_eq!(table.best(), 0);
+
+        // It still remembers where to save
+        table.insert(entry("ABC", 100));
+        table.save().unwrap();
+        assert_eq!(HighScoreTable::load_from(path).entries, vec![entry("ABC", 100)]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt_file_is_moved_aside() {
+        let dir = scratch_dir("corrupt");
+        let path = dir.join("highscores.json");
+        fs::write(&path, "{ not json").unwrap();
+        let table = HighScoreTable::load_from(path.clone());
+        assert!(table.entries.is_empty());
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(dir.join("highscores.json.corrupt")).unwrap(), "{ not json");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn hand_edited_file_is_sanitized() {
+        let dir = scratch_dir("sanitized");
+        let path = dir.join("highscores.json");
+        let entries: Vec<HighScoreEntry> = (1..=12).map(|score| entry("x1", score * 10)).collect();
+        fs::write(&path, serde_json::to_string(&HighScoreTable { entries, path: None }).unwrap()).unwrap();
+        let table = HighScoreTable::load_from(path);
+        assert_eq!(table.entries.len(), MAX_ENTRIES);
+        assert_eq!(table.entries[0], entry("XAA", 120));
+        assert_eq!(table.entries.last().unwrap().score, 30);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn dates_from_days() {
+        assert_eq!(date_from_days(0), "1970-01-01");
+        assert_eq!(date_from_days(-1), "1969-12-31");
+        assert_eq!(date_from_days(10_956), "1999-12-31");
+        assert_eq!(date_from_days(11_016), "2000-02-29");
+        assert_eq!(date_from_days(20_745), "2026-10-19");
+        assert_eq!(date_from_days(47_541), "2100-03-01");
+    }
+}
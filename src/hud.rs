@@ -0,0 +1,152 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+
+// HUD text elements that get updated
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum HudText {
+    Score(usize), // 1UP or 2UP
+    HighScore,
+    Level,
+    Lives,
+}
+
+#[derive(Component)]
+pub struct FruitHistoryRow;
+
+// Constants
+const HUD_FONT_SIZE: f32 = 20.0;
+const FRUIT_ICON_SIZE: f32 = 16.0;
+
+// Score and high score along the top, level, lives and recent fruit along the bottom
+pub fn setup_hud(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let text_style = TextStyle {
+        font,
+        font_size: HUD_FONT_SIZE,
+        color: theme.hud.text,
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::SpaceBetween,
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|root| {
+            // Top bar
+            root.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|bar| {
+                bar.spawn((
+                    TextBundle::from_section("1UP 0", text_style.clone()),
+                    HudText::Score(0),
+                ));
+                bar.spawn((
+                    TextBundle::from_section("HIGH SCORE 0", text_style.clone()),
+                    HudText::HighScore,
+                ));
+                bar.spawn((
+                    TextBundle::from_section("", text_style.clone()),
+                    HudText::Score(1),
+                ));
+            });
+
+            // Bottom bar
+            root.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|bar| {
+                bar.spawn((
+                    TextBundle::from_section("LEVEL 1", text_style.clone()),
+                    HudText::Level,
+                ));
+                bar.spawn((
+                    TextBundle::from_section("LIVES 3", text_style.clone()),
+                    HudText::Lives,
+                ));
+                bar.spawn((
+                    NodeBundle {
+                        style: Style {
+                            column_gap: Val::Px(4.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    FruitHistoryRow,
+                ));
+            });
+        });
+}
+
+// The high score follows the current score live once it is beaten
+// The player whose turn it is has their score highlighted
+pub fn hud_score_system(
+    stats: Res<GameStats>,
+    high_scores: Res<HighScoreTable>,
+    players: Res<Players>,
+    theme: Res<Theme>,
+    mut text_query: Query<(&mut Text, &HudText)>,
+) {
+    if !stats.is_changed() && !high_scores.is_changed() && !players.is_changed() && !theme.is_changed() {
+        return;
+    }
+    for (mut text, hud_text) in text_query.iter_mut() {
+        if let HudText::Score(player) = *hud_text {
+            text.sections[0].style.color = if player == players.current && players.count > 1 { theme.hud.highlight } else { theme.hud.text };
+        }
+        text.sections[0].value = match hud_text {
+            HudText::Score(player) => match players.score(*player, &stats) {
+                Some(score) => format!("{} {}", Players::label(*player), score),
+                None => String::new(),
+            },
+            HudText::HighScore => format!("HIGH SCORE {}", high_scores.best().max(stats.score)),
+            HudText::Level => format!("LEVEL {}", stats.level),
+            HudText::Lives => format!("LIVES {}", stats.lives),
+        };
+    }
+}
+
+// Redraw the fruit icons whenever the history changes, newest on the right
+pub fn hud_fruit_history_system(
+    mut commands: Commands,
+    tracker: Res<FruitTracker>,
+    theme: Res<Theme>,
+    row_query: Query<Entity, With<FruitHistoryRow>>,
+) {
+    if !tracker.is_changed() && !theme.is_changed() {
+        return;
+    }
+    for row in row_query.iter() {
+        commands.entity(row).despawn_descendants().with_children(|row| {
+            for fruit in tracker.history.iter() {
+                row.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(FRUIT_ICON_SIZE),
+                        height: Val::Px(FRUIT_ICON_SIZE),
+                        ..default()
+                    },
+                    background_color: theme.fruit(*fruit).into(),
+                    ..default()
+                });
+            }
+        });
+    }
+}
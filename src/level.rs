@@ -0,0 +1,36 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+
+// Per-level settings, looked up from the level table
+#[derive(Debug, Clone, Copy)]
+pub struct LevelSpec {
+    pub fruit: FruitKind,
+    pub pellet_skip_ticks: u32,       // Ticks Pac-Man stands still after eating a pellet
+    pub power_pellet_skip_ticks: u32, // Same for a power pellet
+    pub elroy_pellets: [usize; 2],    // Blinky speeds up when this few pellets remain, first and second stage
+    pub house_dot_limits: [usize; 3], // Pellets Pinky, Inky and Clyde each wait for before leaving the house
+    pub house_idle_seconds: f32,      // Time without a pellet eaten that forces the next ghost out
+}
+
+// Arcade level table, the last entry repeats forever
+const LEVEL_TABLE: [LevelSpec; 13] = [
+    LevelSpec { fruit: FruitKind::Cherry, pellet_skip_ticks: 1, power_pellet_skip_ticks: 3, elroy_pellets: [20, 10], house_dot_limits: [0, 30, 60], house_idle_seconds: 4.0 },
+    LevelSpec { fruit: FruitKind::Strawberry, pellet_skip_ticks: 1, power_pellet_skip_ticks: 3, elroy_pellets: [30, 15], house_dot_limits: [0, 0, 50], house_idle_seconds: 4.0 },
+    LevelSpec { fruit: FruitKind::Peach, pellet_skip_ticks: 1, power_pellet_skip_ticks: 3, elroy_pellets: [40, 20], house_dot_limits: [0, 0, 0], house_idle_seconds: 4.0 },
+    LevelSpec { fruit: FruitKind::Peach, pellet_skip_ticks: 1, power_pellet_skip_ticks: 3, elroy_pellets: [40, 20], house_dot_limits: [0, 0, 0], house_idle_seconds: 4.0 },
+    LevelSpec { fruit: FruitKind::Apple, pellet_skip_ticks: 1, power_pellet_skip_ticks: 3, elroy_pellets: [40, 20], house_dot_limits: [0, 0, 0], house_idle_seconds: 3.0 },
+    LevelSpec { fruit: FruitKind::Apple, pellet_skip_ticks: 1, power_pellet_skip_ticks: 3, elroy_pellets: [50, 25], house_dot_limits: [0, 0, 0], house_idle_seconds: 3.0 },
+    LevelSpec { fruit: FruitKind::Melon, pellet_skip_ticks: 1, power_pellet_skip_ticks: 3, elroy_pellets: [50, 25], house_dot_limits: [0, 0, 0], house_idle_seconds: 3.0 },
+    LevelSpec { fruit: FruitKind::Melon, pellet_skip_ticks: 1, power_pellet_skip_ticks: 3, elroy_pellets: [50, 25], house_dot_limits: [0, 0, 0], house_idle_seconds: 3.0 },
+    LevelSpec { fruit: FruitKind::Galaxian, pellet_skip_ticks: 1, power_pellet_skip_ticks: 3, elroy_pellets: [60, 30], house_dot_limits: [0, 0, 0], house_idle_seconds: 3.0 },
+    LevelSpec { fruit: FruitKind::Galaxian, pellet_skip_ticks: 1, power_pellet_skip_ticks: 3, elroy_pellets: [60, 30], house_dot_limits: [0, 0, 0], house_idle_seconds: 3.0 },
+    LevelSpec { fruit: FruitKind::Bell, pellet_skip_ticks: 1, power_pellet_skip_ticks: 3, elroy_pellets: [60, 30], house_dot_limits: [0, 0, 0], house_idle_seconds: 3.0 },
+    LevelSpec { fruit: FruitKind::Bell, pellet_skip_ticks: 1, power_pellet_skip_ticks: 3, elroy_pellets: [80, 40], house_dot_limits: [0, 0, 0], house_idle_seconds: 3.0 },
+    LevelSpec { fruit: FruitKind::Key, pellet_skip_ticks: 1, power_pellet_skip_ticks: 3, elroy_pellets: [80, 40], house_dot_limits: [0, 0, 0], house_idle_seconds: 3.0 },
+];
+
+// Levels start at 1
+pub fn level_spec(level: u32) -> LevelSpec {
+    let index = (level.max(1) as usize - 1).min(LEVEL_TABLE.len() - 1);
+    LEVEL_TABLE[index]
+}
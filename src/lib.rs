@@ -0,0 +1,76 @@
+// Modules
+pub mod map;
+pub mod node;
+pub mod pacman;
+pub mod gameplay;
+pub mod ghost;
+pub mod ghost_house;
+pub mod pathfinding;
+pub mod collision;
+pub mod pellet;
+pub mod fruit;
+pub mod level;
+pub mod hud;
+pub mod audio;
+pub mod highscore;
+pub mod validation;
+pub mod editor;
+pub mod rng;
+pub mod controller;
+pub mod simulation;
+pub mod env;
+pub mod save;
+pub mod players;
+pub mod ghost_player;
+pub mod title;
+pub mod net;
+pub mod telemetry;
+pub mod maze_gen;
+pub mod maze_format;
+pub mod theme;
+
+// Prelude
+pub mod prelude {
+    // Crates
+    pub use bevy::{prelude::*, sprite::MaterialMesh2dBundle}; // 0.14
+    pub use std::collections::{HashMap, HashSet};
+    pub use std::fs::File;
+    pub use std::io::{self, BufRead};
+    pub use std::path::Path;
+    // Tile-Based Grid Constants:
+    // Our tiles are going to be 16x16 pixels and the
+    // screen will be 448x512, so 28 columns and 32 rows
+    pub const TILE_SIZE: f32 = 16.0;
+    pub const SCREEN_WIDTH: f32 = 464.0;
+    pub const SCREEN_HEIGHT: f32 = 512.0;
+
+    pub use crate::map::*;
+    pub use crate::pacman::*;
+    pub use crate::node::*;
+    pub use crate::gameplay::*;
+    pub use crate::ghost::*;
+    pub use crate::ghost_house::*;
+    pub use crate::pathfinding::*;
+    pub use crate::collision::*;
+    pub use crate::pellet::*;
+    pub use crate::fruit::*;
+    pub use crate::level::*;
+    pub use crate::hud::*;
+    pub use crate::audio::*;
+    pub use crate::highscore::*;
+    pub use crate::validation::*;
+    pub use crate::editor::*;
+    pub use crate::rng::*;
+    pub use crate::controller::*;
+    pub use crate::simulation::*;
+    pub use crate::env::*;
+    pub use crate::save::*;
+    pub use crate::players::*;
+    pub use crate::ghost_player::*;
+    pub use crate::title::*;
+    pub use crate::net::*;
+    pub use crate::telemetry::*;
+    pub use crate::maze_gen::*;
+    pub use crate::maze_format::*;
+    pub use crate::theme::*;
+}
@@ -1,72 +1,85 @@
-// Modules
-mod map;
-use map::Map;
-mod node;
-use node::NodeGroup;
-use node::Maze;
-mod pacman;
-use pacman::Pacman;
-mod gameplay;
+// Game modules live in the library so the tools in src/bin can share them
+use pacman_bevy::*;
+use pacman_bevy::prelude::*;
+use bevy::input::common_conditions::input_just_pressed;
 
 
-
-// Prelude
-mod prelude {
-    // Crates
-    pub use bevy::{prelude::*, sprite::MaterialMesh2dBundle}; // 0.14
-    pub use std::collections::HashMap;
-    pub use std::fs::File;
-    pub use std::io::{self, BufRead};
-    pub use std::path::Path;
-    // Tile-Based Grid Constants:
-    // Our tiles are going to be 16x16 pixels and the
-    // screen will be 448x512, so 28 columns and 32 rows
-    pub const TILE_SIZE: f32 = 16.0;
-    pub const SCREEN_WIDTH: f32 = 464.0;
-    pub const SCREEN_HEIGHT: f32 = 512.0;
-
-    // Colors
-    pub const YELLOW: Color = Color::srgb(1.0, 1.0, 0.0);
-    pub const WHITE: Color = Color::srgb(255.0, 255.0, 255.0);
-    pub const RED: Color = Color::srgb(255.0, 0.0, 0.0);
-
-    pub use crate::map::*;
-    pub use crate::pacman::*;
-    pub use crate::node::*;
-    pub use crate::gameplay::*;
-}
-
-use prelude::*;
-
 // Main
 pub fn main() {
     // Create the Bevy App/Game
-    App::new()
-        .add_plugins(
+    let mut app = App::new();
+    app.add_plugins(
             DefaultPlugins
         )
+        .add_plugins(GameAudioPlugin)
+        .add_plugins(SimulationPlugin)
+        .add_plugins(TelemetryPlugin)
         .insert_resource(Map::new())
-        .insert_resource(NodeGroup::new())
-        // .insert_resource(Maze::new("/assets/mazes/maze_test.txt"))
+        .insert_resource(GameRng::from_env())
+        .insert_resource(PacmanController::from_env())
+        .insert_resource(HighScoreTable::load())
+        // Drawing only, once the game has built the level
         .add_systems(
             Startup, 
             (
                 map::setup_map_system,
-                node::load_maze,
-                // node::setup_node_group,
-                node::maze_to_nodes,
-                node::assign_neighbors,
                 node::render_nodes_as_quads,
                 spawn_camera,
-                pacman::Pacman::spawn_pacman
-            ).chain()
+                hud::setup_hud,
+            )
+                .chain()
+                .after(ghost::Ghost::spawn_ghosts)
         )
+        // Title screen
+        .add_systems(OnEnter(GameState::Title), title::setup_title)
+        .add_systems(Update, title::title_system.run_if(in_state(GameState::Title)))
+        .add_systems(OnExit(GameState::Title), highscore::despawn_screen::<TitleScreen>)
+        .add_systems(Update, ghost_player::ghost_player_input_system.before(ghost::ghost_movement_system))
+        // High score entry and game over screens
+        .add_systems(OnEnter(GameState::NameEntry), highscore::setup_name_entry)
+        .add_systems(Update, highscore::name_entry_system.run_if(in_state(GameState::NameEntry)))
+        .add_systems(OnExit(GameState::NameEntry), highscore::despawn_screen::<NameEntryScreen>)
+        .add_systems(OnEnter(GameState::GameOver), highscore::setup_game_over)
+        .add_systems(Update, highscore::game_over_system.run_if(in_state(GameState::GameOver)))
+        .add_systems(OnExit(GameState::GameOver), (
+            highscore::despawn_screen::<GameOverScreen>,
+            gameplay::new_game_system,
+        ))
+        // Maze editor
+        .add_systems(Update, editor::toggle_editor_system)
+        .add_systems(Update, controller::toggle_controller_system)
+        // Quick save and load
+        .add_systems(Update, save::save_game_system.run_if(input_just_pressed(KeyCode::F5)))
+        .add_systems(Update, save::load_game_system.run_if(input_just_pressed(KeyCode::F9)))
+        .add_systems(OnEnter(GameState::Editor), editor::setup_editor)
         .add_systems(Update, (
-            gameplay::pacman_input_system,
-            //gameplay::pacman_collision_based_movement_system
-            gameplay::pacman_node_based_movement_system,
+            editor::editor_input_system,
+            editor::editor_refresh_system,
+            editor::editor_gizmo_system,
         )
-        //.chain()
+            .chain()
+            .run_if(in_state(GameState::Editor))
         )
-        .run();
+        .add_systems(OnExit(GameState::Editor), (
+            editor::teardown_editor,
+            editor::despawn_level,
+            node::maze_to_nodes,
+            node::assign_neighbors,
+            node::render_nodes_as_quads,
+            pacman::Pacman::spawn_pacman,
+            ghost::Ghost::spawn_ghosts,
+            gameplay::new_game_system,
+        ).chain())
+        .add_systems(Update, (
+            ghost::ghost_appearance_system,
+            hud::hud_score_system,
+            hud::hud_fruit_history_system,
+        ))
+        .add_systems(PostUpdate, theme::apply_theme_system);
+
+    // Join a network game instead of playing locally
+    if let Some(client) = NetClient::from_env() {
+        app.add_plugins(NetClientPlugin).insert_resource(client);
+    }
+    app.run();
 }
\ No newline at end of file
@@ -5,9 +5,6 @@ use crate::prelude::*;
 const MAP_WIDTH: usize = (SCREEN_WIDTH / TILE_SIZE) as usize; // Update to divide screen
 const MAP_HEIGHT: usize = (SCREEN_HEIGHT / TILE_SIZE) as usize;
 
-#[derive(Component)]
-struct Tile;
-
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TileType {
     Wall,
@@ -36,14 +33,12 @@ impl Map {
         let mut grid = vec![vec![TileType::Path; MAP_WIDTH]; MAP_HEIGHT];
     
         // Set up the Borders
-        for x in 0..MAP_WIDTH {
-            grid[0][x] = TileType::Wall; // Top Row
-            grid[MAP_HEIGHT - 1][x] = TileType::Wall; // Bottom Row
-        }
+        grid[0].fill(TileType::Wall); // Top Row
+        grid[MAP_HEIGHT - 1].fill(TileType::Wall); // Bottom Row
     
-        for y in 0..MAP_HEIGHT {
-            grid[y][0] = TileType::Wall; // Left Column
-            grid[y][MAP_WIDTH - 1] = TileType::Wall; // Right Column
+        for row in grid.iter_mut() {
+            row[0] = TileType::Wall; // Left Column
+            row[MAP_WIDTH - 1] = TileType::Wall; // Right Column
         }
     
         grid
@@ -53,20 +48,18 @@ impl Map {
         let mut grid = vec![vec![TileType::Path; MAP_WIDTH]; MAP_HEIGHT];
     
         // Set up the Borders
-        for x in 0..MAP_WIDTH {
-            grid[0][x] = TileType::Wall; // Top Row
-            grid[MAP_HEIGHT - 1][x] = TileType::Wall; // Bottom Row
-        }
+        grid[0].fill(TileType::Wall); // Top Row
+        grid[MAP_HEIGHT - 1].fill(TileType::Wall); // Bottom Row
     
-        for y in 0..MAP_HEIGHT {
-            grid[y][0] = TileType::Wall; // Left Column
-            grid[y][MAP_WIDTH - 1] = TileType::Wall; // Right Column
+        for row in grid.iter_mut() {
+            row[0] = TileType::Wall; // Left Column
+            row[MAP_WIDTH - 1] = TileType::Wall; // Right Column
         }
     
         grid
     }
 
-    pub fn setup_debug_map(&self, mut commands: Commands, asset_server: Res<AssetServer>) {
+    pub fn setup_debug_map(&self, mut commands: Commands, asset_server: Res<AssetServer>, theme: &Theme) {
         for (y, row) in self.grid.iter().enumerate() {
             for (x, tile) in row.iter().enumerate() {
                 let x_position = x as f32 * TILE_SIZE - SCREEN_WIDTH / 2.0 + TILE_SIZE / 2.0;
@@ -74,9 +67,9 @@ impl Map {
                 // println!("x = {}, y = {}, xval = {}, yval = {}", x, y, x_position, y_position);
     
                 let color = match tile {
-                    TileType::Wall => Color::srgb(0.0, 0.0, 1.0),  // Blue for walls
-                    TileType::Path => Color::srgb(0.0, 0.0, 0.0),  // Black for paths
-                    TileType::Pellet => Color::srgb(255.0, 255.0, 255.0), // WHITE for pellets
+                    TileType::Wall => theme.wall,
+                    TileType::Path => theme.background,
+                    TileType::Pellet => theme.pellet,
                 };
     
                 commands.spawn(SpriteBundle {
@@ -93,7 +86,7 @@ impl Map {
                 if let TileType::Path = tile {
                     commands.spawn(SpriteBundle {
                         sprite: Sprite {
-                            color: Color::rgb(1.0, 1.0, 0.0), // Yellow border
+                            color: theme.grid,
                             custom_size: Some(Vec2::splat(TILE_SIZE)), // Full tile size for border
                             ..Default::default()
                         },
@@ -125,7 +118,7 @@ impl Map {
                     TextStyle {
                         font: font_handle.clone(),
                         font_size: TILE_SIZE / 2.0,
-                        color: Color::rgb(0.5, 1.0, 0.5),
+                        color: theme.hud.text,
                     },
                 );
         
@@ -144,7 +137,7 @@ impl Map {
                     TextStyle {
                         font: font_handle.clone(),
                         font_size: TILE_SIZE / 2.0,
-                        color: Color::rgb(0.5, 1.0, 0.5),
+                        color: theme.hud.text,
                     },
                 );
         
@@ -169,9 +162,15 @@ impl Map {
 
 }
 
+impl Default for Map {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // System
-pub fn setup_map_system(commands: Commands, map: Res<Map>, asset_server: Res<AssetServer>) {
-    map.setup_debug_map(commands, asset_server);
+pub fn setup_map_system(commands: Commands, map: Res<Map>, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    map.setup_debug_map(commands, asset_server, &theme);
 }
 
 // Camera setup system
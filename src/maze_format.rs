@@ -0,0 +1,353 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use base64::Engine;
+use serde::Deserialize;
+
+// Maze files other than our own space separated format, all turned into it before parsing
+// Dense: one character per cell, as used by most other Pac-Man clones
+//   '#' wall, '.' pellet, 'o' power pellet, '-' ghost door, ' ' empty path
+//   Our S, F, H and T also work; without an H the home goes two cells below the door
+//   Any other character is an error
+// JSON: {"rows": [...], "pacman_spawn": [x, y], "fruit": [x, y], "home": [x, y]}
+//   rows are in either text format, the three markers are optional
+// Tiled TMX: the layer named "maze" (or the first layer), CSV or uncompressed base64
+//   A tile's class (or type) picks its cell, otherwise tile ids follow TMX_CELLS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MazeFormat {
+    Tokens,
+    Dense,
+    Json,
+    Tmx,
+}
+
+// Cell names for TMX tiles, in default tile id order
+const TMX_CELLS: [(&str, &str); 9] = [
+    ("wall", "X"),
+    ("pellet", "."),
+    ("power_pellet", "p"),
+    ("path", "-"),
+    ("door", "="),
+    ("home", "H"),
+    ("tunnel", "T"),
+    ("fruit", "F"),
+    ("pacman_spawn", "S"),
+];
+const TMX_FLIP_FLAGS: u32 = 0xE000_0000; // High bits of a gid mark flipped tiles
+const DENSE_HOME_OFFSET: usize = 2; // Rows from the door down to the middle of the house
+
+#[derive(Deserialize)]
+struct JsonMaze {
+    rows: Vec<String>,
+    pacman_spawn: Option<(usize, usize)>,
+    fruit: Option<(usize, usize)>,
+    home: Option<(usize, usize)>,
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+impl MazeFormat {
+    // By extension first, then by what the file starts with; walls give dense mazes away
+    pub fn detect(path: &Path, contents: &str) -> Self {
+        let extension = path.extension().and_then(|extension| extension.to_str()).map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("json") => return MazeFormat::Json,
+            Some("tmx") => return MazeFormat::Tmx,
+            _ => {}
+        }
+        let start = contents.trim_start();
+        if start.starts_with('{') {
+            MazeFormat::Json
+        } else if start.starts_with('<') {
+            MazeFormat::Tmx
+        } else {
+            Self::detect_text(contents.lines())
+        }
+    }
+
+    // Our own format always has a space between cells and never uses '#',
+    // so a '#' anywhere or two cells side by side make it a dense maze
+    fn detect_text<'a>(mut lines: impl Iterator<Item = &'a str>) -> Self {
+        let dense_line = |line: &str| {
+            let line = line.trim();
+            line.contains('#') || line.as_bytes().windows(2).any(|pair| pair.iter().all(|byte| !byte.is_ascii_whitespace()))
+        };
+        if lines.any(dense_line) {
+            MazeFormat::Dense
+        } else {
+            MazeFormat::Tokens
+        }
+    }
+
+    // A maze file and the format it turned out to be in
+    pub fn read(path: &str) -> io::Result<(Maze, Self)> {
+        let contents = std::fs::read_to_string(path)?;
+        let format = Self::detect(Path::new(path), &contents);
+        format.parse(&contents).map(|maze| (maze, format))
+    }
+
+    pub fn parse(self, contents: &str) -> io::Result<Maze> {
+        match self {
+            MazeFormat::Tokens => Ok(Maze::from_map_str(contents)),
+            MazeFormat::Dense => {
+                let mut tokens = dense_tokens(contents.lines())?;
+                place_dense_home(&mut tokens);
+                Ok(Self::from_tokens(tokens, true))
+            }
+            MazeFormat::Json => parse_json(contents),
+            MazeFormat::Tmx => parse_tmx(contents),
+        }
+    }
+
+    // Dense mazes never mark nodes, so they are placed once the grid is built
+    fn from_tokens(tokens: Vec<Vec<&str>>, place_nodes: bool) -> Maze {
+        let map = tokens.iter().map(|row| row.join(" ")).collect::<Vec<_>>().join("\n");
+        let mut maze = Maze::from_map_str(&map);
+        if place_nodes {
+            maze.detect_nodes();
+        }
+        maze
+    }
+}
+
+fn dense_tokens<'a>(lines: impl Iterator<Item = &'a str>) -> io::Result<Vec<Vec<&'static str>>> {
+    lines
+        .enumerate()
+        .map(|(y, line)| {
+            line.trim_end_matches(['\r', '\n'])
+                .chars()
+                .enumerate()
+                .map(|(x, symbol)| match symbol {
+                    '#' => Ok("X"),
+                    '.' => Ok("."),
+                    'o' | 'O' => Ok("p"),
+                    '-' => Ok("="),
+                    ' ' => Ok("-"),
+                    'S' => Ok("S"),
+                    'F' => Ok("F"),
+                    'H' => Ok("H"),
+                    'T' => Ok("T"),
+                    _ => Err(invalid(format!("unknown character {:?} at {:?}", symbol, (x, y)))),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// The arcade house is three rows deep under its door
+fn place_dense_home(tokens: &mut [Vec<&str>]) {
+    let has_home = tokens.iter().flatten().any(|&token| token == "H");
+    let door = tokens
+        .iter()
+        .enumerate()
+        .find_map(|(y, row)| row.iter().position(|&token| token == "=").map(|x| (x, y)));
+    if let (false, Some((x, y))) = (has_home, door) {
+        if let Some(cell) = tokens.get_mut(y + DENSE_HOME_OFFSET).and_then(|row| row.get_mut(x)) {
+            if *cell == "-" {
+                *cell = "H";
+            }
+        }
+    }
+}
+
+fn parse_json(contents: &str) -> io::Result<Maze> {
+    let json: JsonMaze = serde_json::from_str(contents).map_err(|e| invalid(e.to_string()))?;
+    let lines = || json.rows.iter().map(String::as_str);
+    let dense = MazeFormat::detect_text(lines()) == MazeFormat::Dense;
+    let mut tokens: Vec<Vec<&str>> = if dense {
+        dense_tokens(lines())?
+    } else {
+        lines().map(|line| line.split_whitespace().collect()).collect()
+    };
+
+    for ((x, y), token) in [(json.pacman_spawn, "S"), (json.fruit, "F"), (json.home, "H")]
+        .into_iter()
+        .filter_map(|(cell, token)| cell.map(|cell| (cell, token)))
+    {
+        let cell = tokens
+            .get_mut(y)
+            .and_then(|row| row.get_mut(x))
+            .ok_or_else(|| invalid(format!("{} marker {:?} is outside the maze", token, (x, y))))?;
+        *cell = token;
+    }
+    if dense {
+        place_dense_home(&mut tokens);
+    }
+    Ok(MazeFormat::from_tokens(tokens, dense))
+}
+
+fn parse_tmx(contents: &str) -> io::Result<Maze> {
+    let document = roxmltree::Document::parse(contents).map_err(|e| invalid(e.to_string()))?;
+    let map = document.root_element();
+    if !map.has_tag_name("map") {
+        return Err(invalid("not a Tiled map"));
+    }
+
+    // Cells of the first tileset, by global tile id
+    let mut cells: HashMap<u32, &str> = HashMap::new();
+    if let Some(tileset) = map.children().find(|node| node.has_tag_name("tileset")) {
+        let first_gid: u32 = tileset.attribute("firstgid").and_then(|gid| gid.parse().ok()).unwrap_or(1);
+        for (id, &(_, token)) in TMX_CELLS.iter().enumerate() {
+            cells.insert(first_gid + id as u32, token);
+        }
+        for tile in tileset.children().filter(|node| node.has_tag_name("tile")) {
+            let Some(id) = tile.attribute("id").and_then(|id| id.parse::<u32>().ok()) else {
+                continue;
+            };
+            let class = tile.attribute("class").or_else(|| tile.attribute("type"));
+            if let Some(&(_, token)) = TMX_CELLS.iter().find(|&&(name, _)| Some(name) == class) {
+                cells.insert(first_gid + id, token);
+            }
+        }
+    }
+
+    let layers: Vec<_> = map.children().filter(|node| node.has_tag_name("layer")).collect();
+    let layer = layers
+        .iter()
+        .find(|layer| layer.attribute("name") == Some("maze"))
+        .or(layers.first())
+        .ok_or_else(|| invalid("map has no tile layer"))?;
+    let width: usize = layer
+        .attribute("width")
+        .and_then(|width| width.parse().ok())
+        .ok_or_else(|| invalid("layer has no width"))?;
+    let data = layer
+        .children()
+        .find(|node| node.has_tag_name("data"))
+        .ok_or_else(|| invalid("layer has no data"))?;
+    let text = data.text().unwrap_or_default();
+
+    let gids: Vec<u32> = match (data.attribute("encoding"), data.attribute("compression")) {
+        (Some("csv"), _) => text
+            .split(',')
+            .map(|gid| gid.trim().parse().map_err(|_| invalid(format!("bad tile id {:?}", gid.trim()))))
+            .collect::<io::Result<_>>()?,
+        (Some("base64"), None) => base64::engine::general_purpose::STANDARD
+            .decode(text.trim())
+            .map_err(|e| invalid(e.to_string()))?
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect(),
+        (encoding, compression) => {
+            return Err(invalid(format!(
+                "unsupported layer encoding {:?} with compression {:?}, save it as CSV",
+                encoding, compression
+            )))
+        }
+    };
+    if width == 0 {
+        return Err(invalid("layer has no width"));
+    }
+
+    // Empty and unknown tiles are walls
+    let tokens: Vec<Vec<&str>> = gids
+        .chunks(width)
+        .map(|row| row.iter().map(|gid| cells.get(&(gid & !TMX_FLIP_FLAGS)).copied().unwrap_or("X")).collect())
+        .collect();
+    Ok(MazeFormat::from_tokens(tokens, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_dense_characters_are_errors() {
+        let error = MazeFormat::Dense.parse("####\n#.q#\n####").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("'q' at (2, 1)"), "{}", error);
+    }
+
+    #[test]
+    fn detects_text_formats() {
+        let detect = |contents: &str| MazeFormat::detect(Path::new("maze.txt"), contents);
+        assert_eq!(detect("X X X\nX . X\nX X X"), MazeFormat::Tokens);
+        assert_eq!(detect("###\n#.#\n###"), MazeFormat::Dense);
+        // No walls at all, but the cells sit side by side
+        assert_eq!(detect("...\n. .\n..."), MazeFormat::Dense);
+        assert_eq!(detect("{\"rows\": []}"), MazeFormat::Json);
+        assert_eq!(detect("<map></map>"), MazeFormat::Tmx);
+        assert_eq!(MazeFormat::detect(Path::new("maze.tmx"), "X X"), MazeFormat::Tmx);
+    }
+
+    const DENSE: &str = "#######\n#o...S#\n#.#-#.#\n#.# #.#\n#.# #.#\n#..F..#\n#######";
+
+    #[test]
+    fn parses_dense_mazes() {
+        let maze = MazeFormat::Dense.parse(DENSE).unwrap();
+        assert_eq!((maze.width(), maze.height()), (7, 7));
+        assert_eq!(maze.cell(0, 0), MazeCell::Empty);
+        assert_eq!(maze.pellets.get(&(1, 1)), Some(&PelletKind::PowerPellet));
+        assert_eq!(maze.pellets.get(&(2, 1)), Some(&PelletKind::Pellet));
+        assert_eq!(maze.cell(3, 2), MazeCell::Door);
+        // No H in the file, so the home goes two rows under the door
+        assert_eq!(maze.cell(3, 4), MazeCell::Home);
+        assert_eq!(maze.pacman_spawn, Some((5, 1)));
+        assert_eq!(maze.fruit_tile, Some((3, 5)));
+        // Nodes are placed at the corners and junctions
+        assert!(maze.cell(1, 1).is_node() && maze.cell(5, 5).is_node());
+        assert_eq!(maze.cell(1, 3), MazeCell::Path);
+    }
+
+    #[test]
+    fn parses_json_mazes() {
+        let json = r#"{"rows": ["X X X X", "X + n X", "X X X X"], "pacman_spawn": [2, 1], "fruit": [1, 1]}"#;
+        let maze = MazeFormat::Json.parse(json).unwrap();
+        assert_eq!(maze.pacman_spawn, Some((2, 1)));
+        assert_eq!(maze.fruit_tile, Some((1, 1)));
+
+        // Dense rows work too, and are told apart the same way as files
+        let rows: Vec<&str> = DENSE.lines().collect();
+        let dense = serde_json::json!({ "rows": rows, "home": [3, 3] }).to_string();
+        let maze = MazeFormat::Json.parse(&dense).unwrap();
+        assert_eq!(maze.cell(3, 3), MazeCell::Home);
+        assert_eq!(maze.cell(3, 4), MazeCell::Path);
+
+        let outside = r#"{"rows": ["X X", "X X"], "fruit": [5, 0]}"#;
+        assert!(MazeFormat::Json.parse(outside).is_err());
+    }
+
+    fn tmx(data: &str, encoding: &str) -> String {
+        format!(
+            r#"<?xml version="1.0"?>
+<map width="3" height="3">
+  <tileset firstgid="1" name="maze"><tile id="8" class="door"/></tileset>
+  <layer name="decoration" width="3" height="3"><data encoding="csv">1,1,1,1,1,1,1,1,1</data></layer>
+  <layer name="maze" width="3" height="3"><data encoding="{}">{}</data></layer>
+</map>"#,
+            encoding, data
+        )
+    }
+
+    // Wall all round a pellet, a flipped power pellet and a door (tile 8 reclassed from Pac-Man spawn)
+    const TMX_GIDS: [u32; 9] = [1, 1, 1, 2, 0x8000_0003, 9, 1, 1, 1];
+
+    fn check_tmx(maze: &Maze) {
+        assert_eq!((maze.width(), maze.height()), (3, 3));
+        assert_eq!(maze.cell(0, 0), MazeCell::Empty);
+        assert_eq!(maze.pellets.get(&(0, 1)), Some(&PelletKind::Pellet));
+        assert_eq!(maze.pellets.get(&(1, 1)), Some(&PelletKind::PowerPellet));
+        assert_eq!(maze.cell(2, 1), MazeCell::Door);
+    }
+
+    #[test]
+    fn parses_tmx_csv_layers() {
+        let csv: Vec<String> = TMX_GIDS.iter().map(u32::to_string).collect();
+        check_tmx(&MazeFormat::Tmx.parse(&tmx(&csv.join(","), "csv")).unwrap());
+    }
+
+    #[test]
+    fn parses_tmx_base64_layers() {
+        let bytes: Vec<u8> = TMX_GIDS.iter().flat_map(|gid| gid.to_le_bytes()).collect();
+        let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+        check_tmx(&MazeFormat::Tmx.parse(&tmx(&data, "base64")).unwrap());
+    }
+
+    #[test]
+    fn rejects_compressed_tmx_layers() {
+        let compressed = tmx("eJxjYGBgAAAABAAB", "base64").replace(r#"encoding="base64""#, r#"encoding="base64" compression="zlib""#);
+        assert!(MazeFormat::Tmx.parse(&compressed).is_err());
+        assert!(MazeFormat::Tmx.parse("<tileset/>").is_err());
+    }
+}
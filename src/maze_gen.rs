@@ -0,0 +1,258 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::VecDeque;
+
+// Procedural mazes in the same shape as the arcade one: 28 x 31, mirrored left to right,
+// a ghost house in the middle with its door on top, and side tunnels
+// Corridors run along a fixed lattice of rows and columns; starting from every lattice corridor,
+// random ones are walled off as long as no junction is left a dead end and everything stays connected
+//   let maze = generate_maze(&mut GameRng::new(seed));
+
+// Constants
+const WIDTH: usize = 28;
+const HEIGHT: usize = 31;
+const LATTICE_COLUMNS: [usize; 4] = [1, 6, 9, 12]; // Left half, mirrored onto the right
+const LATTICE_ROWS: [usize; 10] = [1, 5, 8, 11, 14, 17, 20, 23, 26, 29];
+const TUNNEL_ROWS: [usize; 5] = [8, 11, 14, 17, 20];
+const TUNNEL_MOUTH: usize = 6; // Lattice column the tunnel runs out from
+const WALL_CHANCE: f64 = 0.5; // Odds of trying to wall off each corridor
+
+// The ghost house sits inside a corridor ring from (9, 11) to (18, 17)
+const RING: ((usize, usize), (usize, usize)) = ((9, 11), (18, 17));
+const HOUSE_EXIT_ROW: usize = 11;
+const DOOR_ROW: usize = 12;
+const HOME: (usize, usize) = (13, 14);
+const HOUSE_ROW: (usize, usize, usize) = (14, 11, 16); // Row, first and last cell of the house corridor
+const FRUIT: (usize, usize) = (13, 17);
+const PACMAN_SPAWN: (usize, usize) = (13, 23);
+const POWER_PELLET_CORNERS: [(usize, usize); 2] = [(1, 3), (1, 26)];
+
+type Cell = (usize, usize);
+type Corridor = (Cell, Cell); // Two lattice points, left/top one first
+
+fn mirror((x, y): Cell) -> Cell {
+    (WIDTH - 1 - x, y)
+}
+
+fn mirror_corridor((a, b): Corridor) -> Corridor {
+    let (a, b) = (mirror(a), mirror(b));
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+// Columns between a tunnel mouth and the edge of the maze
+fn outside_mouths(x: usize) -> bool {
+    !(TUNNEL_MOUTH..=WIDTH - 1 - TUNNEL_MOUTH).contains(&x)
+}
+
+fn in_ring((x, y): Cell) -> bool {
+    let ((left, top), (right, bottom)) = RING;
+    (left..=right).contains(&x) && (top..=bottom).contains(&y)
+}
+
+// Corridors on the ring around the house, the center of the spawn row, and the tunnels stay open
+fn is_fixed((a, b): Corridor, tunnels: &[usize]) -> bool {
+    let ((left, top), (right, bottom)) = RING;
+    let on_ring = |(x, y): Cell| ((x == left || x == right) && (top..=bottom).contains(&y))
+        || ((y == top || y == bottom) && (left..=right).contains(&x));
+    let spawn_row = a.1 == PACMAN_SPAWN.1 && b.1 == PACMAN_SPAWN.1 && a.0 < PACMAN_SPAWN.0 && b.0 > PACMAN_SPAWN.0;
+    let tunnel = a.0 == 0 && tunnels.contains(&a.1);
+    (on_ring(a) && on_ring(b)) || spawn_row || tunnel
+}
+
+// Cells of a straight corridor, both ends included
+fn corridor_cells((a, b): Corridor) -> Vec<Cell> {
+    if a.1 == b.1 {
+        (a.0..=b.0).map(|x| (x, a.1)).collect()
+    } else {
+        (a.1..=b.1).map(|y| (a.0, y)).collect()
+    }
+}
+
+// Every lattice point with at least two corridors out of it, the tunnel mouths counted as corridors
+fn lattice(tunnels: &[usize]) -> HashSet<Corridor> {
+    let columns: Vec<usize> = LATTICE_COLUMNS.iter().copied().chain(LATTICE_COLUMNS.iter().rev().map(|&x| WIDTH - 1 - x)).collect();
+    let mut points: HashSet<Cell> = HashSet::new();
+    for &y in LATTICE_ROWS.iter() {
+        for &x in columns.iter() {
+            let point = (x, y);
+            // Nothing inside the house, and the tunnel takes the outer column on its row
+            let ((left, top), (right, bottom)) = RING;
+            let inside_house = in_ring(point) && x != left && x != right && y != top && y != bottom;
+            let in_tunnel = tunnels.contains(&y) && outside_mouths(x);
+            if !inside_house && !in_tunnel {
+                points.insert(point);
+            }
+        }
+    }
+
+    let mut corridors = HashSet::new();
+    for &(x, y) in points.iter() {
+        let right = columns.iter().find(|&&next| next > x).map(|&next| (next, y));
+        let down = LATTICE_ROWS.iter().find(|&&next| next > y).map(|&next| (x, next));
+        for next in [right, down].into_iter().flatten() {
+            if points.contains(&next) {
+                corridors.insert(((x, y), next));
+            }
+        }
+    }
+    for &y in tunnels.iter() {
+        let tunnel = ((0, y), (TUNNEL_MOUTH, y));
+        corridors.insert(tunnel);
+        corridors.insert(mirror_corridor(tunnel));
+    }
+
+    // Points the tunnels cut off from one side would be dead ends; drop them until none are left
+    loop {
+        let degrees = degrees(&corridors);
+        let dead_ends: Vec<Cell> = degrees
+            .iter()
+            .filter(|&(&(x, _), &degree)| degree < 2 && x != 0 && x != WIDTH - 1)
+            .map(|(&point, _)| point)
+            .collect();
+        if dead_ends.is_empty() {
+            return corridors;
+        }
+        corridors.retain(|(a, b)| !dead_ends.contains(a) && !dead_ends.contains(b));
+    }
+}
+
+fn degrees(corridors: &HashSet<Corridor>) -> HashMap<Cell, usize> {
+    let mut degrees = HashMap::new();
+    for &(a, b) in corridors.iter() {
+        *degrees.entry(a).or_insert(0) += 1;
+        *degrees.entry(b).or_insert(0) += 1;
+    }
+    degrees
+}
+
+fn is_connected(corridors: &HashSet<Corridor>) -> bool {
+    let mut links: HashMap<Cell, Vec<Cell>> = HashMap::new();
+    for &(a, b) in corridors.iter() {
+        links.entry(a).or_default().push(b);
+        links.entry(b).or_default().push(a);
+    }
+    let Some(&start) = links.keys().next() else {
+        return true;
+    };
+    let mut seen = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(point) = queue.pop_front() {
+        for &next in links[&point].iter() {
+            if seen.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    seen.len() == links.len()
+}
+
+// A new maze from the seed's sequence; the same rng state always gives the same maze
+pub fn generate_maze(rng: &mut GameRng) -> Maze {
+    let tunnel_count = if rng.gen_bool(0.5) { 2 } else { 1 };
+    let mut tunnels: Vec<usize> = TUNNEL_ROWS.choose_multiple(rng, tunnel_count).copied().collect();
+    tunnels.sort();
+
+    let mut corridors = lattice(&tunnels);
+
+    // Wall off random corridors on the left, along with their mirror image
+    let mut left: Vec<Corridor> = corridors
+        .iter()
+        .copied()
+        .filter(|&corridor| corridor.0.0 < WIDTH / 2 && !is_fixed(corridor, &tunnels))
+        .collect();
+    left.sort();
+    left.shuffle(rng);
+    for corridor in left {
+        if !rng.gen_bool(WALL_CHANCE) {
+            continue;
+        }
+        let pair = [corridor, mirror_corridor(corridor)];
+        let mut walled = corridors.clone();
+        for side in pair {
+            walled.remove(&side);
+        }
+        let degrees = degrees(&walled);
+        let keeps_junctions = pair
+            .iter()
+            .flat_map(|&(a, b)| [a, b])
+            .all(|point| degrees.get(&point).copied().unwrap_or(0) >= 2);
+        if keeps_junctions && is_connected(&walled) {
+            corridors = walled;
+        }
+    }
+
+    let mut tokens = vec![vec!["X"; WIDTH]; HEIGHT];
+    for &corridor in corridors.iter() {
+        let in_tunnel = corridor.0.0 == 0 || corridor.1.0 == WIDTH - 1;
+        for (x, y) in corridor_cells(corridor) {
+            tokens[y][x] = if in_tunnel && outside_mouths(x) {
+                if x <= 1 || x >= WIDTH - 2 { "n" } else { "T" }
+            } else if in_ring((x, y)) {
+                "-"
+            } else {
+                "."
+            };
+        }
+    }
+
+    // Power pellets go on the pellet cells nearest the corners
+    for corner in POWER_PELLET_CORNERS {
+        let mut pellets: Vec<Cell> = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH / 2).map(move |x| (x, y)))
+            .filter(|&(x, y)| tokens[y][x] == ".")
+            .collect();
+        pellets.sort_by_key(|&(x, y)| x.abs_diff(corner.0) + y.abs_diff(corner.1));
+        if let Some(&(x, y)) = pellets.first() {
+            let (mx, my) = mirror((x, y));
+            tokens[y][x] = "p";
+            tokens[my][mx] = "p";
+        }
+    }
+
+    // The house: a door in the top of the ring, home just below it, a node either side of home
+    for x in [HOME.0, WIDTH - 1 - HOME.0] {
+        tokens[HOUSE_EXIT_ROW][x] = "-";
+        tokens[DOOR_ROW][x] = "=";
+    }
+    for row in tokens[DOOR_ROW + 1..HOME.1].iter_mut() {
+        row[HOME.0] = "-";
+    }
+    let (row, first, last) = HOUSE_ROW;
+    tokens[row][first..=last].fill("-");
+    tokens[row][first] = "n";
+    tokens[row][last] = "n";
+    tokens[HOME.1][HOME.0] = "H";
+    tokens[FRUIT.1][FRUIT.0] = "F";
+    tokens[PACMAN_SPAWN.1][PACMAN_SPAWN.0] = "S";
+
+    let map = tokens.iter().map(|row| row.join(" ")).collect::<Vec<_>>().join("\n");
+    let mut maze = Maze::from_map_str(&map);
+    // Nodes go wherever the corridors turn or meet
+    maze.detect_nodes();
+    maze
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_mazes_are_valid() {
+        for seed in 0..50 {
+            let report = validate_maze(&generate_maze(&mut GameRng::new(seed)));
+            assert!(report.is_valid(), "seed {}: {:?}", seed, report.problems());
+        }
+    }
+
+    #[test]
+    fn same_seed_same_maze() {
+        let first = generate_maze(&mut GameRng::new(7));
+        assert_eq!(first, generate_maze(&mut GameRng::new(7)));
+    }
+}
@@ -0,0 +1,528 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, VecDeque};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+// LAN play over UDP: one authoritative server runs the rules at the fixed arcade tick,
+// two clients draw what it sends and send back what their player is pressing
+// Lockstep: the server only simulates tick N once both clients have sent their input for it,
+// and answers each client with what changed since the last state that client applied
+// A client that goes quiet for CLIENT_TIMEOUT stops holding the game up; its player gets no input
+// until someone joins again and takes the seat over
+//   cargo run --bin pacman-server -- 127.0.0.1:7777
+//   PACMAN_CONNECT=127.0.0.1:7777 cargo run    (first to join is Pac-Man, second steers Blinky)
+
+// Constants
+pub const DEFAULT_SERVER_ADDRESS: &str = "127.0.0.1:7777";
+const CONNECT_VARIABLE: &str = "PACMAN_CONNECT";
+const PLAYERS: usize = 2; // Pac-Man, then Blinky
+const MAX_PACKET: usize = 65_507;
+const HISTORY_TICKS: usize = 240; // Pellet sets kept to diff against
+const JOIN_RETRY_FRAMES: u32 = 30;
+const GAME_OVER_LINGER: Duration = Duration::from_secs(2);
+const RESEND_INTERVAL: Duration = Duration::from_millis(100); // Between resends of a state that wasn't acked
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum ClientMessage {
+    Join,
+    // What the player is pressing for a tick, and the last tick this client has shown
+    Input {
+        tick: u64,
+        direction: PacManDirection,
+        ack: Option<u64>,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum ServerMessage {
+    Welcome { player: usize },
+    Full,
+    State(Box<StateDelta>),
+}
+
+// The game after a tick; actors are small enough to send whole, pellets go as changes
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StateDelta {
+    pub tick: u64,
+    pub base_tick: Option<u64>, // Pellet changes are against this tick, None lists every pellet
+    pub state: GameState,
+    pub stats: GameStats,
+    pub fruit_tracker: FruitTracker,
+    pub fruit: Option<Fruit>,
+    pub pacman: SavedPacman,
+    pub ghosts: Vec<SavedGhost>,
+    pub pellets_removed: Vec<(usize, usize)>,
+    pub pellets_added: Vec<(usize, usize)>,
+}
+
+fn send<T: Serialize>(socket: &UdpSocket, address: SocketAddr, message: &T) {
+    let bytes = match serde_json::to_vec(message) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("Failed to encode message: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = socket.send_to(&bytes, address) {
+        eprintln!("Failed to send to {}: {}", address, e);
+    }
+}
+
+// Everything waiting on the socket; anything that doesn't parse is dropped
+fn receive_all<T: DeserializeOwned>(socket: &UdpSocket) -> Vec<(SocketAddr, T)> {
+    let mut buffer = vec![0; MAX_PACKET];
+    let mut messages = Vec::new();
+    loop {
+        match socket.recv_from(&mut buffer) {
+            Ok((length, address)) => {
+                if let Ok(message) = serde_json::from_slice(&buffer[..length]) {
+                    messages.push((address, message));
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+            // The other end went away; each of these is reported once, keep reading
+            Err(e) if matches!(e.kind(), io::ErrorKind::ConnectionRefused | io::ErrorKind::ConnectionReset) => {}
+            Err(e) => {
+                eprintln!("Failed to receive: {}", e);
+                break;
+            }
+        }
+    }
+    messages
+}
+
+struct RemoteClient {
+    address: SocketAddr,
+    wants_tick: u64, // Highest tick this client has sent input for
+    direction: PacManDirection,
+    ack: Option<u64>,
+    last_heard: Instant,
+    last_sent: Option<Instant>,
+}
+
+impl RemoteClient {
+    fn new(address: SocketAddr) -> Self {
+        RemoteClient {
+            address,
+            wants_tick: 0,
+            direction: PacManDirection::Stop,
+            ack: None,
+            last_heard: Instant::now(),
+            last_sent: None,
+        }
+    }
+
+    fn timed_out(&self) -> bool {
+        self.last_heard.elapsed() > CLIENT_TIMEOUT
+    }
+}
+
+// Runs the headless game and keeps both clients in step with it
+pub struct GameServer {
+    socket: UdpSocket,
+    app: App,
+    agent: AgentController, // Plays Pac-Man's input
+    clients: Vec<RemoteClient>,
+    tick: u64,
+    history: VecDeque<(u64, BTreeSet<(usize, usize)>)>,
+    next_tick: Instant,
+    finished: Option<Instant>,
+}
+
+impl GameServer {
+    pub fn bind(address: &str, seed: u64) -> io::Result<Self> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_nonblocking(true)?;
+
+        let mut app = headless_app(seed);
+        let agent = AgentController::install(&mut app);
+        app.update(); // Startup builds the level
+        app.world_mut().resource_mut::<GhostPlayer>().ghost = Some(GhostName::Blinky);
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Ready);
+
+        let mut server = GameServer {
+            socket,
+            app,
+            agent,
+            clients: Vec::new(),
+            tick: 0,
+            history: VecDeque::new(),
+            next_tick: Instant::now(),
+            finished: None,
+        };
+        server.record_pellets();
+        Ok(server)
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    // Last tick simulated
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    // Serve until the game is over
+    pub fn run(&mut self) -> io::Result<()> {
+        info!("Server listening on {}, waiting for {} players", self.socket.local_addr()?, PLAYERS);
+        while !self.poll() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        Ok(())
+    }
+
+    // Read what the clients sent and simulate the next tick if it is due; true once the game is over
+    pub fn poll(&mut self) -> bool {
+        for (address, message) in receive_all::<ClientMessage>(&self.socket) {
+            self.handle(address, message);
+        }
+
+        // Clients that went quiet don't hold the others up, but with nobody left there's no game
+        let everyone_ready = self.clients.len() == PLAYERS
+            && self.clients.iter().any(|client| !client.timed_out())
+            && self.clients.iter().all(|client| client.wants_tick > self.tick || client.timed_out());
+        if self.finished.is_none() && everyone_ready && Instant::now() >= self.next_tick {
+            self.step();
+            // Never try to catch up on ticks lost waiting for a slow client
+            let tick_length = Duration::from_secs_f32(1.0 / TICKS_PER_SECOND);
+            self.next_tick = (self.next_tick + tick_length).max(Instant::now());
+            for index in 0..self.clients.len() {
+                self.send_state(index);
+            }
+            if self.is_over() {
+                info!("Game over at tick {}", self.tick);
+                self.finished = Some(Instant::now());
+            }
+        }
+
+        self.finished.is_some_and(|at| at.elapsed() > GAME_OVER_LINGER)
+    }
+
+    fn handle(&mut self, address: SocketAddr, message: ClientMessage) {
+        let index = self.clients.iter().position(|client| client.address == address);
+        if let Some(index) = index {
+            self.clients[index].last_heard = Instant::now();
+        }
+        match (message, index) {
+            (ClientMessage::Join, Some(player)) => send(&self.socket, address, &ServerMessage::Welcome { player }),
+            (ClientMessage::Join, None) => {
+                // A new seat while there is one, otherwise the seat of someone who went quiet
+                let player = if self.clients.len() < PLAYERS {
+                    self.clients.push(RemoteClient::new(address));
+                    self.clients.len() - 1
+                } else if let Some(player) = self.clients.iter().position(RemoteClient::timed_out) {
+                    self.clients[player] = RemoteClient::new(address);
+                    player
+                } else {
+                    send(&self.socket, address, &ServerMessage::Full);
+                    return;
+                };
+                info!("Player {} joined from {}", player + 1, address);
+                send(&self.socket, address, &ServerMessage::Welcome { player });
+            }
+            (ClientMessage::Input { tick, direction, ack }, Some(index)) => {
+                let client = &mut self.clients[index];
+                // Datagrams can arrive out of order, the newest input wins
+                if tick >= client.wants_tick {
+                    client.wants_tick = tick;
+                    client.direction = direction;
+                }
+                client.ack = client.ack.max(ack);
+                // Still behind: the last state may have been lost, send it again now and then
+                let resend_due = client.last_sent.is_none_or(|at| at.elapsed() >= RESEND_INTERVAL);
+                if client.ack != Some(self.tick) && resend_due {
+                    self.send_state(index);
+                }
+            }
+            (ClientMessage::Input { .. }, None) => {}
+        }
+    }
+
+    // One fixed tick with the inputs both players sent for it; nothing from a player who went quiet
+    fn step(&mut self) {
+        let direction = |client: &RemoteClient| if client.timed_out() { PacManDirection::Stop } else { client.direction };
+        let pacman_direction = direction(&self.clients[0]);
+        let ghost_direction = direction(&self.clients[1]);
+        self.agent.set(pacman_direction);
+        let world = self.app.world_mut();
+        if ghost_direction != PacManDirection::Stop {
+            world.resource_mut::<GhostPlayer>().wanted = ghost_direction;
+        }
+        self.app.update();
+        self.tick += 1;
+        self.record_pellets();
+    }
+
+    fn record_pellets(&mut self) {
+        let pellets = self.app.world().resource::<PelletGroup>().tiles().collect();
+        self.history.push_back((self.tick, pellets));
+        if self.history.len() > HISTORY_TICKS {
+            self.history.pop_front();
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        let state = self.app.world().resource::<State<GameState>>().get();
+        matches!(state, GameState::NameEntry | GameState::GameOver)
+    }
+
+    fn send_state(&mut self, index: usize) {
+        let Some((_, current)) = self.history.back() else {
+            return;
+        };
+        let client = &self.clients[index];
+        let base = client.ack.and_then(|ack| self.history.iter().find(|(tick, _)| *tick == ack));
+        let (base_tick, pellets_removed, pellets_added) = match base {
+            Some((tick, pellets)) => (
+                Some(*tick),
+                pellets.difference(current).copied().collect(),
+                current.difference(pellets).copied().collect(),
+            ),
+            None => (None, Vec::new(), current.iter().copied().collect()),
+        };
+        let address = client.address;
+        self.clients[index].last_sent = Some(Instant::now());
+
+        let world = self.app.world_mut();
+        let (pacman, ghosts) = match capture_actors(world) {
+            Ok(actors) => actors,
+            Err(e) => {
+                eprintln!("Failed to capture the game: {}", e);
+                return;
+            }
+        };
+        let fruit = world.query::<&Fruit>().iter(world).next().cloned();
+        let delta = StateDelta {
+            tick: self.tick,
+            base_tick,
+            state: *world.resource::<State<GameState>>().get(),
+            stats: world.resource::<GameStats>().clone(),
+            fruit_tracker: world.resource::<FruitTracker>().clone(),
+            fruit,
+            pacman,
+            ghosts,
+            pellets_removed,
+            pellets_added,
+        };
+        send(&self.socket, address, &ServerMessage::State(Box::new(delta)));
+    }
+}
+
+// Connection to a game server; while present the local rules are switched off
+#[derive(Resource)]
+pub struct NetClient {
+    socket: UdpSocket,
+    server: SocketAddr,
+    pub player: Option<usize>, // 0 is Pac-Man, 1 is Blinky; None until the server answers
+    pub applied_tick: Option<u64>,
+    frames_waiting: u32,
+}
+
+impl NetClient {
+    // PACMAN_CONNECT=<host:port> joins a server instead of playing locally
+    pub fn from_env() -> Option<Self> {
+        let address = std::env::var(CONNECT_VARIABLE).ok()?;
+        match Self::connect(&address) {
+            Ok(client) => Some(client),
+            Err(e) => {
+                eprintln!("Failed to connect to {}: {}", address, e);
+                None
+            }
+        }
+    }
+
+    pub fn connect(address: &str) -> io::Result<Self> {
+        let server = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::other("no address to connect to"))?;
+        let socket = UdpSocket::bind(if server.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" })?;
+        socket.set_nonblocking(true)?;
+        info!("Joining server at {}", server);
+        Ok(NetClient {
+            socket,
+            server,
+            player: None,
+            applied_tick: None,
+            frames_waiting: 0,
+        })
+    }
+}
+
+// A client without a window, e.g. for a bot; step it with update()
+pub fn headless_client(address: &str) -> io::Result<App> {
+    let client = NetClient::connect(address)?;
+    let mut app = unfinished_headless_app(0);
+    app.add_plugins(NetClientPlugin).insert_resource(client);
+    app.finish();
+    app.cleanup();
+    Ok(app)
+}
+
+pub struct NetClientPlugin;
+
+impl Plugin for NetClientPlugin {
+    fn build(&self, app: &mut App) {
+        // The server runs the rules, the client only shows what it is sent
+        app.configure_sets(Update, SimulationSet.run_if(|| false))
+            .add_systems(Startup, skip_title)
+            .add_systems(Update, client_network_system);
+    }
+}
+
+fn skip_title(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Ready);
+}
+
+// Show the newest state from the server and send it this frame's input
+pub fn client_network_system(world: &mut World) {
+    world.resource_scope(|world, mut client: Mut<NetClient>| {
+        for (address, message) in receive_all::<ServerMessage>(&client.socket) {
+            if address != client.server {
+                continue;
+            }
+            match message {
+                ServerMessage::Welcome { player } => {
+                    if client.player.is_none() {
+                        info!("Joined as {}", if player == 0 { "Pac-Man" } else { "Blinky" });
+                    }
+                    client.player = Some(player);
+                    if player == 1 {
+                        world.resource_mut::<GhostPlayer>().ghost = Some(GhostName::Blinky);
+                    }
+                }
+                ServerMessage::Full => warn!("Server is full"),
+                ServerMessage::State(delta) => {
+                    // Old news, or pellet changes against a state we never showed
+                    if client.applied_tick.is_some_and(|tick| delta.tick <= tick)
+                        || (delta.base_tick.is_some() && delta.base_tick != client.applied_tick)
+                    {
+                        continue;
+                    }
+                    match apply_delta(world, &delta) {
+                        Ok(()) => client.applied_tick = Some(delta.tick),
+                        Err(e) => eprintln!("Failed to apply server state: {}", e),
+                    }
+                }
+            }
+        }
+
+        let Some(player) = client.player else {
+            if client.frames_waiting.is_multiple_of(JOIN_RETRY_FRAMES) {
+                send(&client.socket, client.server, &ClientMessage::Join);
+            }
+            client.frames_waiting += 1;
+            return;
+        };
+        let direction = if player == 0 {
+            keyboard_direction(world.resource::<ButtonInput<KeyCode>>())
+        } else {
+            world.resource::<GhostPlayer>().wanted
+        };
+        let input = ClientMessage::Input {
+            tick: client.applied_tick.map_or(1, |tick| tick + 1),
+            direction,
+            ack: client.applied_tick,
+        };
+        send(&client.socket, client.server, &input);
+    });
+}
+
+fn apply_delta(world: &mut World, delta: &StateDelta) -> Result<(), String> {
+    restore_actors(world, &delta.pacman, &delta.ghosts)?;
+    world.insert_resource(delta.stats.clone());
+    world.insert_resource(delta.fruit_tracker.clone());
+    if *world.resource::<State<GameState>>().get() != delta.state {
+        world.resource_mut::<NextState<GameState>>().set(delta.state);
+    }
+
+    let maze = world.resource::<Maze>().clone();
+    let theme = world.resource::<Theme>().clone();
+    world.resource_scope(|world, mut pellet_group: Mut<PelletGroup>| {
+        let mut commands = world.commands();
+        if delta.base_tick.is_none() {
+            pellet_group.restore_pellets(&mut commands, &maze, &theme, delta.pellets_added.iter().copied());
+            return;
+        }
+        for &(x, y) in delta.pellets_removed.iter() {
+            if let Some(pellet) = pellet_group.take(IVec2::new(x as i32, y as i32)) {
+                commands.entity(pellet).despawn();
+            }
+        }
+        for &tile in delta.pellets_added.iter() {
+            pellet_group.add_pellet(&mut commands, &maze, &theme, tile);
+        }
+    });
+    restore_fruit(world, delta.fruit.as_ref(), &maze, &theme);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    // A server and two clients over loopback must end up showing the same game
+    use super::*;
+
+    // Constants
+    const TICKS: u64 = 90;
+    const DEADLINE: Duration = Duration::from_secs(30);
+
+    fn applied(client: &App) -> Option<u64> {
+        client.world().resource::<NetClient>().applied_tick
+    }
+
+    // What a client is showing: the tick, score, actor tiles and pellets left
+    type Shown = (Option<u64>, u32, Vec<IVec2>, BTreeSet<(usize, usize)>);
+
+    fn shown(client: &mut App) -> Shown {
+        let world = client.world_mut();
+        let mut tiles: Vec<IVec2> = world
+            .query_filtered::<&Transform, Or<(With<Pacman>, With<Ghost>)>>()
+            .iter(world)
+            .map(|transform| world_to_tile(transform.translation.truncate()))
+            .collect();
+        tiles.sort_by_key(|tile| (tile.x, tile.y));
+        let pellets = world.resource::<PelletGroup>().tiles().collect();
+        let score = world.resource::<GameStats>().score;
+        (applied(client), score, tiles, pellets)
+    }
+
+    #[test]
+    fn clients_agree_with_each_other() {
+        let mut server = GameServer::bind("127.0.0.1:0", 3).unwrap();
+        let address = server.local_addr().unwrap().to_string();
+        let mut clients = [headless_client(&address).unwrap(), headless_client(&address).unwrap()];
+        let started = Instant::now();
+
+        // Play in lockstep until both clients have shown TICKS ticks
+        while clients.iter().any(|client| applied(client).is_none_or(|tick| tick < TICKS)) {
+            assert!(started.elapsed() < DEADLINE, "stuck at server tick {}", server.tick());
+            server.poll();
+            for client in clients.iter_mut() {
+                client.update();
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        // Stop the server and let the clients catch up with its last tick
+        let last_tick = Some(server.tick());
+        while clients.iter().any(|client| applied(client) != last_tick) {
+            assert!(started.elapsed() < DEADLINE, "clients never reached tick {:?}", last_tick);
+            for client in clients.iter_mut() {
+                client.update();
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        let [first, second] = &mut clients;
+        let first_shows = shown(first);
+        assert_eq!(first_shows.2.len(), 5, "Pac-Man and four ghosts");
+        assert_eq!(first_shows, shown(second));
+        assert_eq!(first.world().resource::<NetClient>().player, Some(0));
+        assert_eq!(second.world().resource::<NetClient>().player, Some(1));
+    }
+}
@@ -1,7 +1,8 @@
 use crate::prelude::*;
+use serde::{Deserialize, Serialize};
 
 // Define direction constants
-#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum PacManDirection {
     Up,
     Down,
@@ -11,6 +12,14 @@ pub enum PacManDirection {
 }
 
 impl PacManDirection {
+    // Every direction that leads somewhere
+    pub const ALL: [PacManDirection; 4] = [
+        PacManDirection::Up,
+        PacManDirection::Left,
+        PacManDirection::Down,
+        PacManDirection::Right,
+    ];
+
     pub fn opposite(&self) -> PacManDirection {
         match *self {
             PacManDirection::Up => PacManDirection::Down,
@@ -20,118 +29,538 @@ impl PacManDirection {
             PacManDirection::Stop => PacManDirection::Stop,
         }
     }
+
+    // Step through the maze grid, rows grow downwards
+    pub fn grid_offset(&self) -> (isize, isize) {
+        match *self {
+            PacManDirection::Up => (0, -1),
+            PacManDirection::Down => (0, 1),
+            PacManDirection::Left => (-1, 0),
+            PacManDirection::Right => (1, 0),
+            PacManDirection::Stop => (0, 0),
+        }
+    }
+
+    // Unit vector in screen space for the direction
+    pub fn to_vec2(&self) -> Vec2 {
+        match *self {
+            PacManDirection::Up => Vec2::new(0.0, 1.0),
+            PacManDirection::Down => Vec2::new(0.0, -1.0),
+            PacManDirection::Left => Vec2::new(-1.0, 0.0),
+            PacManDirection::Right => Vec2::new(1.0, 0.0),
+            PacManDirection::Stop => Vec2::ZERO,
+        }
+    }
 }
 
 // Create a maze resource to be used in node building
 // Define Cell Types
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum MazeCell {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MazeCell {
     Empty,
     Node,
     Path,
+    Door,   // Ghost house door, only ghosts may pass
+    Home,   // Node inside the ghost house where eaten ghosts revive
+    Tunnel, // Path through the side tunnel
+}
+
+impl MazeCell {
+    // Home is a node as far as the graph is concerned
+    pub fn is_node(&self) -> bool {
+        matches!(self, MazeCell::Node | MazeCell::Home)
+    }
+
+    pub fn is_walkable(&self) -> bool {
+        *self != MazeCell::Empty
+    }
 }
 
+pub const DEFAULT_MAZE_PATH: &str = "assets/mazes/maze_test.txt";
+const MAZE_DIRECTORY: &str = "assets/mazes";
+const MAZE_PATH_VARIABLE: &str = "PACMAN_MAZE";
+const MAZE_SEED_VARIABLE: &str = "PACMAN_MAZE_SEED";
+
 #[derive(Debug, Clone, PartialEq, Resource)]
 pub struct Maze {
     grid: Vec<Vec<MazeCell>>,
+    pub pellets: HashMap<(usize, usize), PelletKind>, // Pellets laid out at the start of a level
+    pub fruit_tile: Option<(usize, usize)>, // Where bonus fruit appears
+    pub pacman_spawn: Option<(usize, usize)>, // Node Pac-Man starts each life on
 }
 
 impl Maze {
     
     pub fn new() -> io::Result<Self> {
-        Self::read_map("assets/mazes/maze_test.txt")
+        Self::read_map(DEFAULT_MAZE_PATH)
     }
 
+    // Any format MazeFormat knows, told apart by extension or contents
     pub fn read_map(filename: &str) -> io::Result<Self> {
-        let file = File::open(filename)?;
-        let reader = io::BufReader::new(file);
+        MazeFormat::read(filename).map(|(maze, _)| maze)
+    }
 
+    // Parse the space separated maze format
+    pub fn from_map_str(contents: &str) -> Self {
         let mut grid = Vec::new();
+        let mut pellets = HashMap::new();
+        let mut fruit_tile = None;
+        let mut pacman_spawn = None;
 
-        for line in reader.lines() {
-            let line = line?.trim().to_string();  // Trim whitespace around each line
-            let row: Vec<MazeCell> = line.split_whitespace().map(|c| match c {
-                "X" => MazeCell::Empty,
-                "+" => MazeCell::Node,
-                "." => MazeCell::Path,
-                _ => MazeCell::Empty, // Default to empty for unrecognized chars
-            }).collect();
+        for (y, line) in contents.lines().enumerate() {
+            let line = line.trim();  // Trim whitespace around each line
+            let mut row = Vec::new();
+            for (x, token) in line.split_whitespace().enumerate() {
+                let (cell, pellet) = Self::parse_token(token);
+                if let Some(kind) = pellet {
+                    pellets.insert((x, y), kind);
+                }
+                if token == "F" {
+                    fruit_tile = Some((x, y));
+                }
+                if token == "S" {
+                    pacman_spawn = Some((x, y));
+                }
+                row.push(cell);
+            }
             grid.push(row);
         }
         
-        Ok(Maze { grid })
+        let mut maze = Maze { grid, pellets, fruit_tile, pacman_spawn };
+        // A plain wall/path grid has no '+' at all, work the nodes out instead
+        if maze.node_cells().is_empty() {
+            maze.detect_nodes();
+        }
+        maze
+    }
+
+    // Cell symbols:
+    // nodes '+' (pellet), 'P' (power pellet), 'n' (empty), 'S' (Pac-Man spawn)
+    // paths '.' (pellet), 'p' (power pellet), '-' and '|' (empty)
+    fn parse_token(token: &str) -> (MazeCell, Option<PelletKind>) {
+        match token {
+            "X" => (MazeCell::Empty, None),
+            "+" => (MazeCell::Node, Some(PelletKind::Pellet)),
+            "P" => (MazeCell::Node, Some(PelletKind::PowerPellet)),
+            "n" => (MazeCell::Node, None),
+            "." => (MazeCell::Path, Some(PelletKind::Pellet)),
+            "p" => (MazeCell::Path, Some(PelletKind::PowerPellet)),
+            "-" | "|" => (MazeCell::Path, None),
+            "F" => (MazeCell::Path, None), // Fruit spawn
+            "S" => (MazeCell::Node, None), // Pac-Man spawn
+            "=" => (MazeCell::Door, None),
+            "H" => (MazeCell::Home, None),
+            "T" => (MazeCell::Tunnel, None),
+            _ => (MazeCell::Empty, None), // Default to empty for unrecognized chars
+        }
+    }
+
+    // Inverse of parse_token for a single cell
+    fn token(&self, x: usize, y: usize) -> &'static str {
+        if self.fruit_tile == Some((x, y)) {
+            return "F";
+        }
+        if self.pacman_spawn == Some((x, y)) {
+            return "S";
+        }
+        match (self.grid[y][x], self.pellets.get(&(x, y))) {
+            (MazeCell::Empty, _) => "X",
+            (MazeCell::Node, Some(PelletKind::Pellet)) => "+",
+            (MazeCell::Node, Some(PelletKind::PowerPellet)) => "P",
+            (MazeCell::Node, None) => "n",
+            (MazeCell::Path, Some(PelletKind::Pellet)) => ".",
+            (MazeCell::Path, Some(PelletKind::PowerPellet)) => "p",
+            (MazeCell::Path, None) => "-",
+            (MazeCell::Door, _) => "=",
+            (MazeCell::Home, _) => "H",
+            (MazeCell::Tunnel, _) => "T",
+        }
+    }
+
+    // Write the maze back out in the format read_map expects
+    pub fn to_map_string(&self) -> String {
+        self.grid
+            .iter()
+            .enumerate()
+            .map(|(y, row)| (0..row.len()).map(|x| self.token(x, y)).collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn write_map(&self, filename: &str) -> io::Result<()> {
+        std::fs::write(filename, self.to_map_string() + "\n")
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.iter().map(|row| row.len()).max().unwrap_or(0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.len()
+    }
+
+    // Cells outside the grid (or past the end of a short row) are walls
+    pub fn cell(&self, x: usize, y: usize) -> MazeCell {
+        self.grid.get(y).and_then(|row| row.get(x)).copied().unwrap_or(MazeCell::Empty)
+    }
+
+    // Change a cell, dropping pellets and fruit from cells that can no longer hold them
+    pub fn set_cell(&mut self, x: usize, y: usize, cell: MazeCell) {
+        let Some(slot) = self.grid.get_mut(y).and_then(|row| row.get_mut(x)) else {
+            return;
+        };
+        *slot = cell;
+        if !matches!(cell, MazeCell::Node | MazeCell::Path) {
+            self.pellets.remove(&(x, y));
+            if self.fruit_tile == Some((x, y)) {
+                self.fruit_tile = None;
+            }
+        }
+        if cell != MazeCell::Node && self.pacman_spawn == Some((x, y)) {
+            self.pacman_spawn = None;
+        }
+    }
+
+    pub fn set_pellet(&mut self, x: usize, y: usize, pellet: Option<PelletKind>) {
+        match pellet {
+            Some(kind) if matches!(self.cell(x, y), MazeCell::Node | MazeCell::Path) => {
+                self.pellets.insert((x, y), kind);
+            }
+            _ => {
+                self.pellets.remove(&(x, y));
+            }
+        }
+    }
+
+    // Place nodes at every intersection, corner and dead end, and only there
+    // Pellets stay where they are; Home, Door, Tunnel and the Pac-Man spawn are left alone
+    pub fn detect_nodes(&mut self) {
+        let mut changes = Vec::new();
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let cell = self.cell(x, y);
+                if !matches!(cell, MazeCell::Node | MazeCell::Path) || self.pacman_spawn == Some((x, y)) {
+                    continue;
+                }
+                let wanted = if self.is_junction(x, y) { MazeCell::Node } else { MazeCell::Path };
+                if wanted != cell {
+                    changes.push((x, y, wanted));
+                }
+            }
+        }
+        for (x, y, cell) in changes {
+            self.grid[y][x] = cell;
+        }
+    }
+
+    // Anything but a straight corridor needs a node
+    fn is_junction(&self, x: usize, y: usize) -> bool {
+        let open: Vec<PacManDirection> = PacManDirection::ALL
+            .into_iter()
+            .filter(|direction| {
+                let (dx, dy) = direction.grid_offset();
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                nx >= 0 && ny >= 0 && self.cell(nx as usize, ny as usize).is_walkable()
+            })
+            .collect();
+        match open.as_slice() {
+            [a, b] => *a != b.opposite(), // Corner
+            _ => true, // Dead end, intersection or isolated cell
+        }
+    }
+
+    // Grid positions of every node cell, row by row
+    pub fn node_cells(&self) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for (y, row) in self.grid.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if cell.is_node() {
+                    cells.push((x, y));
+                }
+            }
+        }
+        cells
+    }
+
+    // Walk from a cell to the next node in a direction, stopping at walls
+    // Returns the node cell and whether the path to it crosses the ghost door
+    pub fn next_node(&self, start_x: usize, start_y: usize, direction: PacManDirection) -> Option<((usize, usize), bool)> {
+        self.trace_edge(start_x, start_y, direction).map(|edge| (edge.end, edge.door))
+    }
+
+    // Like next_node, but also report what the corridor is made of
+    pub fn trace_edge(&self, start_x: usize, start_y: usize, direction: PacManDirection) -> Option<MazeEdge> {
+        let (dx, dy) = direction.grid_offset();
+        if (dx, dy) == (0, 0) {
+            return None;
+        }
+        let (mut x, mut y) = (start_x as isize, start_y as isize);
+        let mut edge = MazeEdge {
+            end: (start_x, start_y),
+            length: 0,
+            door: false,
+            tunnel: false,
+            wraps: false,
+        };
+        loop {
+            x += dx;
+            y += dy;
+            edge.length += 1;
+
+            // Walking off the left or right edge comes back in on the other side,
+            // so the tunnel mouths link up; a row that is open all the way across never ends
+            if dx != 0 && (x < 0 || x as usize >= self.width()) && edge.length <= self.width() {
+                x = x.rem_euclid(self.width() as isize);
+                edge.wraps = true;
+                edge.tunnel = true;
+            }
+
+            // Check bounds
+            if x < 0 || y < 0 || x as usize >= self.width() || y as usize >= self.height() {
+                return None;
+            }
+
+            // Check if the cell is a node, stop at walls
+            let (ux, uy) = (x as usize, y as usize);
+            match self.cell(ux, uy) {
+                MazeCell::Empty => return None,
+                MazeCell::Door => edge.door = true,
+                MazeCell::Tunnel => edge.tunnel = true,
+                cell if cell.is_node() => {
+                    edge.end = (ux, uy);
+                    return Some(edge);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Node cells inside the ghost house: everything Home reaches without crossing the door
+    pub fn house_cells(&self) -> HashSet<(usize, usize)> {
+        let mut house = HashSet::new();
+        let mut stack: Vec<(usize, usize)> = self
+            .node_cells()
+            .into_iter()
+            .filter(|&(x, y)| self.cell(x, y) == MazeCell::Home)
+            .collect();
+        while let Some((x, y)) = stack.pop() {
+            if !house.insert((x, y)) {
+                continue;
+            }
+            for direction in PacManDirection::ALL {
+                if let Some(edge) = self.trace_edge(x, y, direction).filter(|edge| !edge.door) {
+                    stack.push(edge.end);
+                }
+            }
+        }
+        house
+    }
+}
+
+// A corridor between two node cells, as found by walking the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MazeEdge {
+    pub end: (usize, usize),
+    pub length: usize, // In tiles
+    pub door: bool,    // Crosses the ghost house door
+    pub tunnel: bool,  // Crosses tunnel cells
+    pub wraps: bool,   // Goes off one side of the maze and back in on the other
+}
+
+// Offsets from grid cells to screen positions
+const X_OFFSET: f32 = -SCREEN_WIDTH / 2.0 + TILE_SIZE;
+const Y_OFFSET: f32 = SCREEN_HEIGHT / 2.0 - TILE_SIZE;
+
+// Screen position of the centre of a maze cell
+pub fn tile_to_world(x: usize, y: usize) -> Vec2 {
+    Vec2::new(x as f32 * TILE_SIZE + X_OFFSET, -(y as f32 * TILE_SIZE) + Y_OFFSET)
+}
+
+// Maze cell containing a screen position (may be outside the maze)
+pub fn world_to_tile(position: Vec2) -> IVec2 {
+    IVec2::new(
+        ((position.x - X_OFFSET) / TILE_SIZE).round() as i32,
+        ((Y_OFFSET - position.y) / TILE_SIZE).round() as i32,
+    )
+}
+
+// Who is travelling along an edge; each edge says which of them may use it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActorKind {
+    Pacman,
+    Ghost,        // Ghost out in the maze
+    GhostEyes,    // Eaten ghost on its way back into the house
+    LeavingGhost, // Ghost on its way out of the house
+}
+
+impl ActorKind {
+    pub const ALL: [ActorKind; 4] = [
+        ActorKind::Pacman,
+        ActorKind::Ghost,
+        ActorKind::GhostEyes,
+        ActorKind::LeavingGhost,
+    ];
+}
+
+// Link from one node to its neighbor in one direction
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub to: Entity,
+    pub length: usize,  // In tiles
+    pub tunnel: bool,   // Crosses tunnel cells
+    pub wraps: bool,    // Leaves one side of the maze and comes in on the other
+    pub one_way: bool,  // Ghost house door: going in and coming out are open to different actors
+    pub access: HashSet<ActorKind>,
+}
+
+impl Edge {
+    pub fn allows(&self, actor: ActorKind) -> bool {
+        self.access.contains(&actor)
     }
 
-    //
+    pub fn pixel_length(&self) -> f32 {
+        self.length as f32 * TILE_SIZE
+    }
 }
 
 // Create individual Node Component
 #[derive(Component)]
 pub struct MapNode {
     pub position: Vec2,
-    pub neighbors: HashMap<PacManDirection, Option<Entity>>, // Neighbor nodes
+    pub edges: HashMap<PacManDirection, Edge>, // Always matched by an edge back from the neighbor
 }
 
 impl MapNode {
     pub fn new(x: f32, y: f32) -> Self {
         MapNode {
             position: Vec2::new(x, y),
-            neighbors: HashMap::from([
-                (PacManDirection::Up, None),
-                (PacManDirection::Down, None),
-                (PacManDirection::Left, None),
-                (PacManDirection::Right, None),
-            ]),
+            edges: HashMap::new(),
         }
     }
+
+    // Maze cell the node sits on
+    pub fn tile(&self) -> (usize, usize) {
+        let tile = world_to_tile(self.position);
+        (tile.x.max(0) as usize, tile.y.max(0) as usize)
+    }
+
+    pub fn edge(&self, direction: PacManDirection) -> Option<&Edge> {
+        self.edges.get(&direction)
+    }
+
+    // Get the neighbor in a direction if the actor may go that way
+    pub fn neighbor(&self, direction: PacManDirection, actor: ActorKind) -> Option<Entity> {
+        self.edge(direction).filter(|edge| edge.allows(actor)).map(|edge| edge.to)
+    }
 }
 
 // Group Nodes together
 #[derive(Resource)]
 pub struct NodeGroup {
     node_list: HashMap<(usize, usize), Entity>,
+    pub home_node: Option<Entity>, // Where eaten ghosts revive
+    pub house_exit: Option<Entity>, // Node just outside the ghost house door
+    pub pacman_spawn: Option<Entity>,
 }
 
 impl NodeGroup {
     pub fn new() -> Self {
         NodeGroup {
             node_list: HashMap::new(),
+            home_node: None,
+            house_exit: None,
+            pacman_spawn: None,
         }
     }
 
+    // Node entity on a maze cell, if that cell is a node
+    pub fn node_at(&self, tile: (usize, usize)) -> Option<Entity> {
+        self.node_list.get(&tile).copied()
+    }
+
     pub fn setup_nodes(
         &mut self, 
         mut commands: Commands, 
         maze: Res<Maze>
     ) {
-        // Determine offsets for each tile
-        let x_offset = -SCREEN_WIDTH / 2.0 + TILE_SIZE;
-        let y_offset = SCREEN_HEIGHT / 2.0 - TILE_SIZE;
+        // Start from scratch, the maze may have been rebuilt
+        self.node_list.clear();
+        self.home_node = None;
+        self.house_exit = None;
+        self.pacman_spawn = None;
 
-        let mut nodes = HashMap::new(); // Store nodes by (x, y) positions
+        // Create nodes for each node cell and store their entities
+        for (x, y) in maze.node_cells() {
+            let position = tile_to_world(x, y);
+            
+            let node_entity = commands.spawn(MapNode::new(position.x, position.y)).id();
+            self.node_list.insert((x,y),node_entity); // Store entity with grid position
+            if maze.cell(x, y) == MazeCell::Home {
+                self.home_node = Some(node_entity);
+            }
+            if maze.pacman_spawn == Some((x, y)) {
+                self.pacman_spawn = Some(node_entity);
+            }
+        }
 
-        // Create nodes for each walkable cell and store their entities
-        for (y, row) in maze.grid.iter().enumerate() {
-            for (x, cell) in row.iter().enumerate() {
-                if *cell == MazeCell::Node {
-                    let x_position = x as f32 * TILE_SIZE + x_offset;
-                    let y_position = -(y as f32 * TILE_SIZE) + y_offset;
-                    
-                    let node_entity = commands.spawn(MapNode::new(x_position, y_position)).id();
-                    nodes.insert((x, y), node_entity); // Store entity with grid position
-                    self.node_list.insert((x,y),node_entity); // Can i make nodes and node list the same thing??
-                }
+    }
+}
+
+impl Default for NodeGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Where the maze being played came from, so the editor knows where it may save it
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub enum MazeSource {
+    File { path: String, format: MazeFormat },
+    Generated { seed: u64 },
+}
+
+impl MazeSource {
+    // Our own format is saved back where it came from; other formats and the shipped default maze
+    // get a new file beside them and generated mazes one of their own
+    pub fn save_path(&self) -> String {
+        match self {
+            MazeSource::File { path, format: MazeFormat::Tokens } if path != DEFAULT_MAZE_PATH => path.clone(),
+            MazeSource::File { path, .. } => {
+                let path = Path::new(path);
+                let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("maze");
+                path.with_file_name(format!("{}_edited.txt", stem)).to_string_lossy().into_owned()
             }
+            MazeSource::Generated { seed } => format!("{}/generated_{}.txt", MAZE_DIRECTORY, seed),
         }
-        
     }
 }
 
 // Systems to Render Nodes on Screen
 pub fn load_maze(mut commands: Commands) {
-    match Maze::new() {
-        Ok(maze) => commands.insert_resource(maze),
-        Err(e) => eprintln!("Failed to load maze: {}", e),
+    // PACMAN_MAZE_SEED plays a generated maze instead of the default one
+    let maze_seed = std::env::var(MAZE_SEED_VARIABLE).ok().and_then(|value| value.trim().parse().ok());
+    if let Some(seed) = maze_seed {
+        info!("Generated maze {}", seed);
+        let source = MazeSource::Generated { seed };
+        commands.insert_resource(generate_maze(&mut GameRng::new(seed)));
+        commands.insert_resource(Theme::for_maze(Some(&source)));
+        commands.insert_resource(source);
+        return;
+    }
+    // PACMAN_MAZE plays a maze file in any supported format
+    let path = std::env::var(MAZE_PATH_VARIABLE).unwrap_or_else(|_| DEFAULT_MAZE_PATH.to_string());
+    match MazeFormat::read(&path) {
+        Ok((maze, format)) => {
+            let source = MazeSource::File { path, format };
+            commands.insert_resource(maze);
+            // Each maze can come with its own colors
+            commands.insert_resource(Theme::for_maze(Some(&source)));
+            commands.insert_resource(source);
+        }
+        Err(e) => {
+            eprintln!("Failed to load maze: {}", e);
+            commands.insert_resource(Theme::for_maze(None));
+        }
     }
 }
 
@@ -139,65 +568,88 @@ pub fn load_maze(mut commands: Commands) {
 pub fn find_next_node(
     start_x: usize,
     start_y: usize,
-    dx: isize,
-    dy: isize,
+    direction: PacManDirection,
     maze: &Maze,
     nodes: &HashMap<(usize, usize), Entity>
-) -> Option<Entity> {
-    let (mut x, mut y) = (start_x as isize, start_y as isize);
-    loop {
-        x += dx;
-        y += dy;
-
-        // Check bounds
-        if x < 0 || y < 0 || x as usize >= maze.grid[0].len() || y as usize >= maze.grid.len() {
-            return None;
-        }
+) -> Option<(Entity, MazeEdge)> {
+    let edge = maze.trace_edge(start_x, start_y, direction)?;
+    nodes.get(&edge.end).map(|&node| (node, edge))
+}
 
-        // Check if the cell is a node
-        let (ux, uy) = (x as usize, y as usize);
-        if maze.grid[uy][ux] == MazeCell::Node {
-            return nodes.get(&(ux, uy)).copied();
-        }
+// Who may walk from one end of a corridor to the other
+// Through the door only eyes go in and only leaving ghosts come out
+fn edge_access(maze_edge: &MazeEdge, entering_house: bool) -> HashSet<ActorKind> {
+    if !maze_edge.door {
+        return ActorKind::ALL.into_iter().collect();
+    }
+    if entering_house {
+        HashSet::from([ActorKind::GhostEyes])
+    } else {
+        HashSet::from([ActorKind::LeavingGhost])
     }
 }
 
-// Define neighbors by checking adjacent cells
+// Link every pair of facing nodes with an edge each way
 pub fn assign_neighbors(
     maze: Res<Maze>,
-    map_nodes: Res<NodeGroup>,
+    mut map_nodes: ResMut<NodeGroup>,
     mut query: Query<&mut MapNode>,
 ) {
+    let house = maze.house_cells();
     for (&(x, y), &node_entity) in map_nodes.node_list.iter() {
-        if let Ok(mut node) = query.get_mut(node_entity) {
-            // Check each direction and assign neighbors if found
-            if let Some(up_neighbor) = find_next_node(x, y, 0, -1, &maze, &map_nodes.node_list) {
-                node.neighbors.insert(PacManDirection::Up, Some(up_neighbor));
-            }
-            if let Some(down_neighbor) = find_next_node(x, y, 0, 1, &maze, &map_nodes.node_list) {
-                node.neighbors.insert(PacManDirection::Down, Some(down_neighbor));
-            }
-            if let Some(left_neighbor) = find_next_node(x, y, -1, 0, &maze, &map_nodes.node_list) {
-                node.neighbors.insert(PacManDirection::Left, Some(left_neighbor));
-            }
-            if let Some(right_neighbor) = find_next_node(x, y, 1, 0, &maze, &map_nodes.node_list) {
-                node.neighbors.insert(PacManDirection::Right, Some(right_neighbor));
+        // Walk Right and Down only so each corridor is traced once and both ends agree on it
+        for direction in [PacManDirection::Right, PacManDirection::Down] {
+            let Some((neighbor, maze_edge)) = find_next_node(x, y, direction, &maze, &map_nodes.node_list) else {
+                continue;
+            };
+            let ends = [
+                (node_entity, neighbor, direction, house.contains(&maze_edge.end)),
+                (neighbor, node_entity, direction.opposite(), house.contains(&(x, y))),
+            ];
+            for (from, to, edge_direction, entering_house) in ends {
+                if let Ok(mut node) = query.get_mut(from) {
+                    node.edges.insert(edge_direction, Edge {
+                        to,
+                        length: maze_edge.length,
+                        tunnel: maze_edge.tunnel,
+                        wraps: maze_edge.wraps,
+                        one_way: maze_edge.door,
+                        access: edge_access(&maze_edge, entering_house),
+                    });
+                }
             }
         }
     }
+
+    // The house exit is whatever the home node reaches through the door
+    let house_exit = map_nodes.home_node
+        .and_then(|home| query.get(home).ok())
+        .and_then(|home| {
+            PacManDirection::ALL.into_iter().find_map(|direction| {
+                home.edge(direction)
+                    .filter(|edge| edge.one_way && edge.allows(ActorKind::LeavingGhost))
+                    .map(|edge| edge.to)
+            })
+        });
+    map_nodes.house_exit = house_exit;
 }
 
 //
 pub fn maze_to_nodes(
-    mut commands: Commands,
+    commands: Commands,
     mut node_group: ResMut<NodeGroup>,
     maze: Res<Maze>,
 ) {
     node_group.setup_nodes(commands, maze);
 }
 
+// Marker for the debug node/link sprites so they can be cleared on rebuild
+#[derive(Component)]
+pub struct NodeQuad;
+
 pub fn render_nodes_as_quads(
     mut commands: Commands,
+    theme: Res<Theme>,
     query: Query<(Entity, &MapNode)>,
 ) {
     for (_, node) in query.iter() {
@@ -205,19 +657,30 @@ pub fn render_nodes_as_quads(
         // Debugging output for node positions
         println!("Rendering Node at Position X: {} Y: {}", node.position.x, node.position.y);
 
-        commands.spawn(SpriteBundle {
-            sprite: Sprite {
-                color: RED,
-                custom_size: Some(Vec2::splat(16.0)), // Adjust size as needed
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: theme.node,
+                    custom_size: Some(Vec2::splat(16.0)), // Adjust size as needed
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(node.position.x, node.position.y, 0.5)),
                 ..default()
             },
-            transform: Transform::from_translation(Vec3::new(node.position.x, node.position.y, 0.5)),
-            ..default()
-        });
+            NodeQuad,
+        ));
 
-        // Draw lines to each neighbor using a quad
-        for neighbor in node.neighbors.values().flatten() {
-            if let Ok((_, neighbor_node)) = query.get(*neighbor) {
+        // Draw lines to each neighbor using a quad, colored by what kind of edge it is
+        // Wraps would draw right across the maze
+        for edge in node.edges.values().filter(|edge| !edge.wraps) {
+            let line_color = if edge.one_way {
+                theme.door
+            } else if edge.tunnel {
+                theme.tunnel
+            } else {
+                theme.path
+            };
+            if let Ok((_, neighbor_node)) = query.get(edge.to) {
                 let start = Vec2::new(node.position.x, node.position.y);
                 let end = Vec2::new(neighbor_node.position.x, neighbor_node.position.y);
 
@@ -234,20 +697,80 @@ pub fn render_nodes_as_quads(
                     start.x, start.y, end.x, end.y
                 );
 
-                commands.spawn(SpriteBundle {
-                    sprite: Sprite {
-                        color: Color::WHITE,
-                        custom_size: Some(Vec2::new(length, 2.0)), // Thin line, adjust thickness if needed
-                        ..default()
-                    },
-                    transform: Transform {
-                        translation: Vec3::new(midpoint.x, midpoint.y, 0.5),
-                        rotation: Quat::from_rotation_z(angle),
+                commands.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: line_color,
+                            custom_size: Some(Vec2::new(length, 2.0)), // Thin line, adjust thickness if needed
+                            ..default()
+                        },
+                        transform: Transform {
+                            translation: Vec3::new(midpoint.x, midpoint.y, 0.5),
+                            rotation: Quat::from_rotation_z(angle),
+                            ..default()
+                        },
                         ..default()
                     },
-                    ..default()
-                });
+                    NodeQuad,
+                ));
             }
         }
     }
-}
\ No newline at end of file
+}
+
+// Node graph for a maze in a world of its own, built the way the game does, for tests
+#[cfg(test)]
+pub(crate) fn build_nodes(maze: &Maze) -> World {
+    let mut world = World::new();
+    world.insert_resource(maze.clone());
+    world.insert_resource(NodeGroup::new());
+    let mut schedule = Schedule::default();
+    schedule.add_systems((maze_to_nodes, assign_neighbors).chain());
+    schedule.run(&mut world);
+    world
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TUNNEL_ROW: &str = "X X X X X\nn T n T n\nX X X X X";
+
+    #[test]
+    fn tunnel_mouths_wrap_both_ways() {
+        let maze = Maze::from_map_str(TUNNEL_ROW);
+        for (start, direction, end) in [((4, 1), PacManDirection::Right, (0, 1)), ((0, 1), PacManDirection::Left, (4, 1))] {
+            let edge = maze.trace_edge(start.0, start.1, direction).unwrap();
+            assert_eq!(edge.end, end);
+            assert_eq!(edge.length, 1);
+            assert!(edge.wraps && edge.tunnel);
+        }
+
+        let world = build_nodes(&maze);
+        let node_group = world.resource::<NodeGroup>();
+        let left = node_group.node_at((0, 1)).unwrap();
+        let right = node_group.node_at((4, 1)).unwrap();
+        let left_edge = world.get::<MapNode>(left).unwrap().edge(PacManDirection::Left).unwrap();
+        let right_edge = world.get::<MapNode>(right).unwrap().edge(PacManDirection::Right).unwrap();
+        assert_eq!((left_edge.to, right_edge.to), (right, left));
+        assert!(left_edge.wraps && left_edge.tunnel && left_edge.allows(ActorKind::Pacman));
+        assert!(right_edge.wraps && right_edge.tunnel && right_edge.allows(ActorKind::Ghost));
+    }
+
+    #[test]
+    fn walls_stop_the_wrap() {
+        let maze = Maze::from_map_str("X X X X X\nX n - n X\nX X X X X");
+        assert_eq!(maze.trace_edge(3, 1, PacManDirection::Right), None);
+        assert_eq!(maze.trace_edge(1, 1, PacManDirection::Left), None);
+    }
+
+    #[test]
+    fn editor_never_saves_over_another_format_or_the_default_maze() {
+        let file = |path: &str, format| MazeSource::File { path: path.to_string(), format };
+        assert_eq!(file(DEFAULT_MAZE_PATH, MazeFormat::Tokens).save_path(), "assets/mazes/maze_test_edited.txt");
+        assert_eq!(file("assets/mazes/mine.txt", MazeFormat::Tokens).save_path(), "assets/mazes/mine.txt");
+        assert_eq!(file("assets/mazes/classic.txt", MazeFormat::Dense).save_path(), "assets/mazes/classic_edited.txt");
+        assert_eq!(file("levels/big.tmx", MazeFormat::Tmx).save_path(), "levels/big_edited.txt");
+        assert_eq!(MazeSource::Generated { seed: 12 }.save_path(), "assets/mazes/generated_12.txt");
+    }
+}
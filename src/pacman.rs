@@ -8,15 +8,19 @@ pub struct Pacman{
     pub node_position: Vec2,
     pub speed: f32,
     // pub vec_direction: Vec2, // Vec with Direction
-    pub node_direction: PacManDirection,  // Up Right Down Left for Node Movement
+    pub node_direction: PacManDirection,  // Up Right Down Left for Node Movement, Stop while standing still
+    pub facing: PacManDirection,          // Last direction moved in, kept while stopped against a wall
     pub queued_direction: Option<PacManDirection>,
-    pub current_node: Entity,
-    pub target_node: Option<Entity>,
-    pub is_reversing: bool,
+    pub current_node: Entity,      // Node last left, or stood on
+    pub target_node: Option<Entity>, // Far end of the edge being walked, None while on a node
+    pub edge_progress: f32,        // Pixels travelled from current_node towards target_node
+    pub edge_length: f32,          // Pixels from current_node to target_node
+    pub start_node: Entity,
 }
 
 // Constants
 const RADIUS: f32 = 8.0;
+const START_FACING: PacManDirection = PacManDirection::Left;
 
 // Implementation
 impl Pacman {
@@ -30,11 +34,28 @@ impl Pacman {
             node_position,
             speed: 100.0 * (TILE_SIZE / 16.0),
             node_direction: PacManDirection::Stop,
+            facing: START_FACING,
             // vec_direction: Vec2::ZERO,
             queued_direction,
             current_node,
             target_node: None,
-            is_reversing: false,
+            edge_progress: 0.0,
+            edge_length: 0.0,
+            start_node: current_node,
+        }
+    }
+
+    // Put Pac-Man back on his spawn node, standing still
+    pub fn respawn(&mut self, node_query: &Query<&MapNode>) {
+        self.current_node = self.start_node;
+        self.target_node = None;
+        self.edge_progress = 0.0;
+        self.edge_length = 0.0;
+        self.queued_direction = None;
+        self.node_direction = PacManDirection::Stop;
+        self.facing = START_FACING;
+        if let Ok(node) = node_query.get(self.start_node) {
+            self.node_position = node.position;
         }
     }
 
@@ -49,18 +70,25 @@ impl Pacman {
     }
 
 
-    // Spawn a new Pac-Man
+    // Spawn a new Pac-Man on the maze's spawn node, or any node if it has none
     pub fn spawn_pacman (
         mut commands: Commands,
+        node_group: Res<NodeGroup>,
+        theme: Res<Theme>,
         node_query: Query<(Entity, &MapNode)>
     ) {
-        if let Some((node_entity, node)) = node_query.iter().next() {
+        let spawn = node_group.pacman_spawn
+            .and_then(|entity| node_query.get(entity).ok())
+            .or_else(|| node_query.iter().next());
+        if let Some((node_entity, node)) = spawn {
             let spawn_node_position = node.position;
             commands.spawn((
                 Pacman::new(spawn_node_position, node_entity, None),
+                TileOccupancy::new(spawn_node_position),
+                SkipTicks::default(),
                 SpriteBundle {
                     sprite: Sprite {
-                        color: YELLOW,
+                        color: theme.pacman,
                         custom_size: Some(Vec2::splat(TILE_SIZE * 0.8)),
                         ..Default::default()
                     },
@@ -71,80 +99,90 @@ impl Pacman {
         }
     }
 
-     // Check if a given direction is valid (i.e., there's a neighbor in that direction)
-     pub fn valid_direction(&self, direction: PacManDirection, node_query: &Query<&MapNode>) -> bool {
-        if let Ok(node) = node_query.get(self.current_node) {
-            return node.neighbors.contains_key(&direction) && direction != PacManDirection::Stop;
-        }
-        false
+    // Check if a given direction is valid (i.e., there's a neighbor in that direction)
+    pub fn valid_direction(&self, direction: PacManDirection, node_query: &Query<&MapNode>) -> bool {
+        node_query
+            .get(self.current_node)
+            .is_ok_and(|node| node.neighbor(direction, ActorKind::Pacman).is_some())
     }
 
     // Get the new target node in a specified direction, or return the current node if invalid
     pub fn get_new_target(&self, direction: PacManDirection, node_query: &Query<&MapNode>) -> Entity {
         if let Ok(node) = node_query.get(self.current_node) {
-            if self.valid_direction(direction, node_query) {
-                if let Some(Some(target_node)) = node.neighbors.get(&direction) {
-                    return *target_node;
-                }
+            // Pac-Man is never allowed through the ghost house door
+            if let Some(target_node) = node.neighbor(direction, ActorKind::Pacman) {
+                return target_node;
             }
         }
         self.current_node // Return current node if no valid target
     }
 
-    /// Sets Pac-Man's direction and updates the target node to the next node in that direction.
-    pub fn set_direction_and_target(
-        &mut self, 
-        new_direction: PacManDirection, 
-        node_query: &Query<&MapNode>,
-        is_reverse: bool,
-    ) {
-        // Update Pac-Man's direction
-        self.node_direction = new_direction;
-
-        let origin_node = self.current_node;
+    // Leave the current node along the edge in a direction, if there is one
+    pub fn start_edge(&mut self, direction: PacManDirection, node_query: &Query<&MapNode>) -> bool {
+        let Some(edge) = node_query
+            .get(self.current_node)
+            .ok()
+            .and_then(|node| node.edge(direction))
+            .filter(|edge| edge.allows(ActorKind::Pacman))
+        else {
+            return false;
+        };
+        self.node_direction = direction;
+        self.facing = direction;
+        self.target_node = Some(edge.to);
+        self.edge_length = edge.pixel_length();
+        self.edge_progress = 0.0;
+        true
+    }
 
-        // Reversing
-        if is_reverse {
-            if let Some(target) = self.target_node{
-                self.current_node = target;
+    // On a node: take the queued turn if it is open, otherwise carry on, otherwise stop
+    // Stopping keeps the facing; only a new key press that leads somewhere starts him again
+    pub fn leave_node(&mut self, node_query: &Query<&MapNode>) -> bool {
+        if let Some(queued_direction) = self.queued_direction {
+            if self.start_edge(queued_direction, node_query) {
+                self.queued_direction = None;
+                return true;
             }
-            self.target_node = Some(origin_node);
         }
-        
-        let next_node = self.get_new_target(new_direction, node_query);
-        
-        // If a valid target node exists, set it as the target node
-        if next_node != self.current_node {
-            self.target_node = Some(next_node);
-        } else {
-            // If no valid target, stop Pac-Man
-            self.node_direction = PacManDirection::Stop;
-            self.target_node = None;
+        if self.start_edge(self.node_direction, node_query) {
+            return true;
         }
+        // Held keys queue themselves again every frame, a stale one shouldn't fire later
+        self.node_direction = PacManDirection::Stop;
+        self.queued_direction = None;
+        false
     }
 
-    // Determine if Pac-Man is going to move past the target node
-    pub fn overshot_target(&self, transform: &Transform, node_query: &Query<&MapNode>) -> bool {
-        if let Ok(target_node) = node_query.get(self.get_new_target(self.node_direction, node_query)) {
-            if let Ok(current_node) = node_query.get(self.current_node) {
-                // Vector from current node to target node and from current node to Pac-Man
-                let vec_to_target = target_node.position - current_node.position;
-                let vec_to_pacman = Vec2::new(transform.translation.x, transform.translation.y) - current_node.position;
-                
-                // Compare distances squared to check if Pac-Man has reached or passed the target node
-                vec_to_pacman.length_squared() >= vec_to_target.length_squared() // || vec_to_pacman.dot(vec_to_target) > 0.0
-            } else {
-                false
-            }
-        } else {
-            false
+    // Reached the end of the edge
+    pub fn arrive(&mut self, node_query: &Query<&MapNode>) {
+        let Some(target) = self.target_node.take() else {
+            return;
+        };
+        self.current_node = target;
+        self.edge_progress = 0.0;
+        self.edge_length = 0.0;
+        if let Ok(node) = node_query.get(target) {
+            self.node_position = node.position;
         }
     }
 
+    // Turn around mid-edge: the far end becomes the node we left from
+    pub fn reverse(&mut self, node_query: &Query<&MapNode>) {
+        let Some(target) = self.target_node else {
+            return;
+        };
+        self.target_node = Some(self.current_node);
+        self.current_node = target;
+        self.node_direction = self.node_direction.opposite();
+        self.facing = self.node_direction;
+        self.edge_progress = (self.edge_length - self.edge_progress).max(0.0);
+        if let Ok(node) = node_query.get(target) {
+            self.node_position = node.position;
+        }
+    }
 
-
+    // Screen position from the node we left and how far along the edge we are
+    pub fn position(&self) -> Vec2 {
+        self.node_position + self.node_direction.to_vec2() * self.edge_progress
+    }
 }
-
-
-
-
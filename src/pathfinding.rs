@@ -0,0 +1,160 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+// Pathfinding over the MapNode graph
+// Dijkstra search in whole tiles, using edge lengths as cost, tunnel wraps included,
+// usable by any actor that moves node to node (eaten ghosts, bots, ...)
+
+// Entry in the open set, ordered so the BinaryHeap pops the lowest cost first
+// and, between equal costs, whichever was pushed first
+struct OpenNode {
+    cost: usize,
+    order: usize,
+    node: Entity,
+}
+
+impl PartialEq for OpenNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OpenNode {}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.cmp(&self.cost).then(other.order.cmp(&self.order))
+    }
+}
+
+// Find the shortest path from start to goal, both ends included
+// Only edges open to the actor are followed, e.g. the door only for ghosts entering/leaving home
+pub fn shortest_path(
+    start: Entity,
+    goal: Entity,
+    node_query: &Query<&MapNode>,
+    actor: ActorKind,
+) -> Option<Vec<Entity>> {
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<Entity, Entity> = HashMap::new();
+    let mut cost_so_far: HashMap<Entity, usize> = HashMap::new();
+
+    cost_so_far.insert(start, 0);
+    open.push(OpenNode { cost: 0, order: 0, node: start });
+    let mut pushed = 1;
+
+    while let Some(OpenNode { cost, node: current, .. }) = open.pop() {
+        if current == goal {
+            // Walk back from the goal to rebuild the path
+            let mut path = vec![goal];
+            let mut step = goal;
+            while let Some(&previous) = came_from.get(&step) {
+                path.push(previous);
+                step = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        // Already reached more cheaply since this entry was pushed
+        let current_cost = cost_so_far[&current];
+        if cost > current_cost {
+            continue;
+        }
+        let Ok(node) = node_query.get(current) else {
+            continue;
+        };
+
+        // Fixed direction order so ties always break the same way
+        let edges = PacManDirection::ALL.into_iter().filter_map(|direction| node.edge(direction));
+        for edge in edges.filter(|edge| edge.allows(actor)) {
+            let next = edge.to;
+            let new_cost = current_cost + edge.length;
+            if cost_so_far.get(&next).is_none_or(|&cost| new_cost < cost) {
+                cost_so_far.insert(next, new_cost);
+                came_from.insert(next, current);
+                open.push(OpenNode { cost: new_cost, order: pushed, node: next });
+                pushed += 1;
+            }
+        }
+    }
+
+    None
+}
+
+// Direction of the first step along the shortest path, or None if already there/unreachable
+pub fn direction_towards(
+    start: Entity,
+    goal: Entity,
+    node_query: &Query<&MapNode>,
+    actor: ActorKind,
+) -> Option<PacManDirection> {
+    let path = shortest_path(start, goal, node_query, actor)?;
+    let next = *path.get(1)?;
+    let node = node_query.get(start).ok()?;
+    PacManDirection::ALL
+        .into_iter()
+        .find(|direction| node.neighbor(*direction, actor) == Some(next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::build_nodes;
+    use bevy::ecs::system::SystemState;
+
+    fn build_world(map: &str) -> World {
+        build_nodes(&Maze::from_map_str(map))
+    }
+
+    fn path_between(world: &mut World, from: (usize, usize), to: (usize, usize), actor: ActorKind) -> Option<Vec<(usize, usize)>> {
+        let node_group = world.resource::<NodeGroup>();
+        let (start, goal) = (node_group.node_at(from)?, node_group.node_at(to)?);
+        let mut state: SystemState<Query<&MapNode>> = SystemState::new(world);
+        let node_query = state.get(world);
+        let path = shortest_path(start, goal, &node_query, actor)?;
+        Some(path.iter().map(|&node| node_query.get(node).unwrap().tile()).collect())
+    }
+
+    #[test]
+    fn equal_routes_break_ties_in_direction_order() {
+        // Down comes before Right, so both routes round the ring go down first
+        let ring = "X X X X X\nX n - n X\nX - X - X\nX n - n X\nX X X X X";
+        for _ in 0..20 {
+            let path = path_between(&mut build_world(ring), (1, 1), (3, 3), ActorKind::Pacman).unwrap();
+            assert_eq!(path, vec![(1, 1), (1, 3), (3, 3)]);
+            let back = path_between(&mut build_world(ring), (3, 3), (1, 1), ActorKind::Pacman).unwrap();
+            assert_eq!(back, vec![(3, 3), (3, 1), (1, 1)]);
+        }
+    }
+
+    #[test]
+    fn only_ghosts_use_the_door_and_only_the_right_way() {
+        // The house at (3, 3) is only reachable through the door below (3, 1)
+        let maze = "X X X X X X X\nX n - n - n X\nX | X = X | X\nX | X H X | X\nX | X X X | X\nX n - - - n X\nX X X X X X X";
+        let mut world = build_world(maze);
+        assert_eq!(path_between(&mut world, (3, 1), (3, 3), ActorKind::Pacman), None);
+        assert_eq!(path_between(&mut world, (3, 1), (3, 3), ActorKind::Ghost), None);
+        assert_eq!(path_between(&mut world, (1, 5), (3, 3), ActorKind::GhostEyes), Some(vec![(1, 5), (1, 1), (3, 1), (3, 3)]));
+
+        // Out of the house the door only opens for a ghost leaving it
+        assert_eq!(path_between(&mut world, (3, 3), (3, 1), ActorKind::GhostEyes), None);
+        assert_eq!(path_between(&mut world, (3, 3), (5, 5), ActorKind::LeavingGhost), Some(vec![(3, 3), (3, 1), (5, 1), (5, 5)]));
+    }
+
+    #[test]
+    fn tunnel_wrap_is_taken_when_shorter() {
+        // Six tiles straight across, five out through the tunnel and back in the other side
+        let row = "X X X X X X X X X X X\nn T n - - - - - n T n\nX X X X X X X X X X X";
+        let path = path_between(&mut build_world(row), (2, 1), (8, 1), ActorKind::GhostEyes).unwrap();
+        assert_eq!(path, vec![(2, 1), (0, 1), (10, 1), (8, 1)]);
+    }
+}
@@ -0,0 +1,157 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PelletKind {
+    Pellet,
+    PowerPellet,
+}
+
+impl PelletKind {
+    pub fn points(&self) -> u32 {
+        match self {
+            PelletKind::Pellet => 10,
+            PelletKind::PowerPellet => 50,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Pellet {
+    pub kind: PelletKind,
+}
+
+// Pac-Man ate a pellet
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PelletEatenEvent {
+    pub kind: PelletKind,
+    pub tile: (usize, usize),
+}
+
+// Group the remaining pellets by grid position
+#[derive(Resource)]
+pub struct PelletGroup {
+    pellet_list: HashMap<(usize, usize), Entity>,
+}
+
+impl PelletGroup {
+    pub fn new() -> Self {
+        PelletGroup {
+            pellet_list: HashMap::new(),
+        }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.pellet_list.len()
+    }
+
+    // Grid positions of the pellets still on the board
+    pub fn tiles(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.pellet_list.keys().copied()
+    }
+
+    // Lay out every pellet in the maze, clearing any left over from the last level
+    pub fn setup_pellets(&mut self, commands: &mut Commands, maze: &Maze, theme: &Theme) {
+        self.restore_pellets(commands, maze, theme, maze.pellets.keys().copied());
+    }
+
+    // Lay out only the given pellets, e.g. what was left when a game was saved
+    pub fn restore_pellets(
+        &mut self,
+        commands: &mut Commands,
+        maze: &Maze,
+        theme: &Theme,
+        tiles: impl IntoIterator<Item = (usize, usize)>,
+    ) {
+        for (_, entity) in self.pellet_list.drain() {
+            commands.entity(entity).despawn();
+        }
+
+        for tile in tiles {
+            self.add_pellet(commands, maze, theme, tile);
+        }
+    }
+
+    // Put back the maze's pellet on a tile, unless it is already there
+    pub fn add_pellet(&mut self, commands: &mut Commands, maze: &Maze, theme: &Theme, (x, y): (usize, usize)) {
+        let Some(&kind) = maze.pellets.get(&(x, y)) else {
+            return;
+        };
+        if self.pellet_list.contains_key(&(x, y)) {
+            return;
+        }
+        let position = tile_to_world(x, y);
+        let size = match kind {
+            PelletKind::Pellet => TILE_SIZE * 0.25,
+            PelletKind::PowerPellet => TILE_SIZE * 0.6,
+        };
+        let pellet_entity = commands.spawn((
+            Pellet { kind },
+            SpriteBundle {
+                sprite: Sprite {
+                    color: theme.pellet(kind),
+                    custom_size: Some(Vec2::splat(size)),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(position.extend(0.6)),
+                ..Default::default()
+            },
+        )).id();
+        self.pellet_list.insert((x, y), pellet_entity);
+    }
+
+    // Remove the pellet on a tile, if there is one
+    pub fn take(&mut self, tile: IVec2) -> Option<Entity> {
+        if tile.x < 0 || tile.y < 0 {
+            return None;
+        }
+        self.pellet_list.remove(&(tile.x as usize, tile.y as usize))
+    }
+}
+
+impl Default for PelletGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Systems
+pub fn spawn_pellets(
+    mut commands: Commands,
+    mut pellet_group: ResMut<PelletGroup>,
+    mut stats: ResMut<GameStats>,
+    maze: Res<Maze>,
+    theme: Res<Theme>,
+) {
+    pellet_group.setup_pellets(&mut commands, &maze, &theme);
+    stats.pellets_remaining = pellet_group.remaining();
+}
+
+// Eat whatever pellet is on Pac-Man's tile
+pub fn pellet_eating_system(
+    mut commands: Commands,
+    mut pellet_group: ResMut<PelletGroup>,
+    mut stats: ResMut<GameStats>,
+    mut pellet_events: EventWriter<PelletEatenEvent>,
+    pacman_query: Query<&TileOccupancy, With<Pacman>>,
+    pellet_query: Query<&Pellet>,
+) {
+    for occupancy in pacman_query.iter() {
+        let Some(pellet_entity) = pellet_group.take(occupancy.current) else {
+            continue;
+        };
+        let Ok(pellet) = pellet_query.get(pellet_entity) else {
+            continue;
+        };
+
+        stats.score += pellet.kind.points();
+        stats.pellets_eaten += 1;
+        stats.pellets_remaining = pellet_group.remaining();
+        pellet_events.send(PelletEatenEvent {
+            kind: pellet.kind,
+            tile: (occupancy.current.x as usize, occupancy.current.y as usize),
+        });
+        commands.entity(pellet_entity).despawn();
+    }
+}
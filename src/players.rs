@@ -0,0 +1,119 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// One player's game, put away while the other player has their turn
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PlayerState {
+    pub stats: GameStats,
+    pub pellets: Vec<(usize, usize)>,
+    pub fruit_tracker: FruitTracker,
+}
+
+impl PlayerState {
+    // Nothing played yet: full maze, all lives
+    pub fn fresh(maze: &Maze) -> Self {
+        let mut stats = GameStats::new();
+        stats.pellets_remaining = maze.pellets.len();
+        PlayerState {
+            stats,
+            pellets: maze.pellets.keys().copied().collect(),
+            fruit_tracker: FruitTracker::new(),
+        }
+    }
+}
+
+// Classic alternating play: the live game always belongs to the current player,
+// the other one waits here until the current player loses a life
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct Players {
+    pub count: usize,   // 1 or 2
+    pub current: usize, // 0 is 1UP, 1 is 2UP
+    pub waiting: Option<PlayerState>,
+}
+
+// Time to hand the maze to the other player
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerSwapEvent;
+
+impl Players {
+    pub fn new() -> Self {
+        Players {
+            count: 1,
+            current: 0,
+            waiting: None,
+        }
+    }
+
+    // Both players start from scratch, 1UP goes first
+    pub fn start(&mut self, count: usize, maze: &Maze) {
+        self.count = count;
+        self.current = 0;
+        self.waiting = (count > 1).then(|| PlayerState::fresh(maze));
+    }
+
+    pub fn label(player: usize) -> &'static str {
+        if player == 0 {
+            "1UP"
+        } else {
+            "2UP"
+        }
+    }
+
+    // The waiting player still has a life to play
+    pub fn other_can_play(&self) -> bool {
+        self.waiting.as_ref().is_some_and(|other| other.stats.lives > 0)
+    }
+
+    // Score of either player, whether they are up or waiting
+    pub fn score(&self, player: usize, stats: &GameStats) -> Option<u32> {
+        if player == self.current {
+            Some(stats.score)
+        } else {
+            self.waiting.as_ref().map(|other| other.stats.score)
+        }
+    }
+}
+
+impl Default for Players {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Put the current player's maze away and bring out the other player's
+#[allow(clippy::too_many_arguments)]
+pub fn player_swap_system(
+    mut commands: Commands,
+    mut swap_events: EventReader<PlayerSwapEvent>,
+    mut players: ResMut<Players>,
+    mut stats: ResMut<GameStats>,
+    mut pellet_group: ResMut<PelletGroup>,
+    mut fruit_tracker: ResMut<FruitTracker>,
+    mut house: ResMut<GhostHouse>,
+    maze: Res<Maze>,
+    theme: Res<Theme>,
+    fruit_query: Query<Entity, With<Fruit>>,
+) {
+    if swap_events.read().count() == 0 {
+        return;
+    }
+    let Some(next) = players.waiting.take() else {
+        return;
+    };
+    players.waiting = Some(PlayerState {
+        stats: stats.clone(),
+        pellets: pellet_group.tiles().collect(),
+        fruit_tracker: fruit_tracker.clone(),
+    });
+    players.current = 1 - players.current;
+    info!("{} is up", Players::label(players.current));
+
+    *stats = next.stats;
+    pellet_group.restore_pellets(&mut commands, &maze, &theme, next.pellets);
+    *fruit_tracker = next.fruit_tracker;
+    for fruit in fruit_query.iter() {
+        commands.entity(fruit).despawn();
+    }
+    *house = GhostHouse::new(stats.level);
+}
@@ -0,0 +1,58 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+// Every random decision in the game draws from here so a run can be reproduced from its seed
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct GameRng {
+    pub seed: u64,
+    rng: ChaCha8Rng,
+}
+
+// Constants
+const SEED_VARIABLE: &str = "PACMAN_SEED";
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    // PACMAN_SEED picks the seed, otherwise a fresh one each run
+    pub fn from_env() -> Self {
+        let seed = std::env::var(SEED_VARIABLE)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or_else(|| rand::thread_rng().next_u64());
+        info!("Random seed {}", seed);
+        Self::new(seed)
+    }
+
+    // Start the same sequence over again
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+}
+
+// Lets GameRng be passed anywhere rand expects an Rng
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
@@ -0,0 +1,342 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+// Everything needed to pick a game back up mid-level
+// Entities don't survive a restart, so actors point at nodes by their maze cell instead;
+// the node graph itself is rebuilt from the maze, which is stored to make sure it still matches
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub maze: String, // In the maze file format
+    pub state: GameState,
+    pub state_timer: Timer,
+    pub stats: GameStats,
+    pub schedule: GhostModeSchedule,
+    pub house: GhostHouse,
+    pub fruit_tracker: FruitTracker,
+    pub players: Players,
+    pub fruit: Option<Fruit>,
+    pub rng: GameRng,
+    pub pellets: Vec<(usize, usize)>,
+    pub pacman: SavedPacman,
+    pub ghosts: Vec<SavedGhost>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SavedPacman {
+    pub current_node: (usize, usize),
+    pub target_node: Option<(usize, usize)>,
+    pub edge_progress: f32,
+    pub edge_length: f32,
+    pub node_direction: PacManDirection,
+    pub facing: PacManDirection,
+    pub queued_direction: Option<PacManDirection>,
+    pub skip_ticks: f32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SavedGhost {
+    pub name: GhostName,
+    pub mode: GhostMode,
+    pub current_node: (usize, usize),
+    pub target_node: Option<(usize, usize)>,
+    pub edge_progress: f32,
+    pub edge_length: f32,
+    pub node_direction: PacManDirection,
+    pub frightened_timer: Timer,
+    pub elroy_stage: usize,
+    pub skip_ticks: f32,
+}
+
+impl SaveGame {
+    // Default location, next to the high score table
+    pub fn default_path() -> PathBuf {
+        dirs::data_dir()
+            .map(|dir| dir.join("pacman_bevy"))
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("savegame.json")
+    }
+
+    // Only a round in progress can be saved, not the editor or the end of game screens
+    pub fn capture(world: &mut World) -> Result<Self, String> {
+        let state = *world.resource::<State<GameState>>().get();
+        if !matches!(state, GameState::Ready | GameState::Playing | GameState::Dying) {
+            return Err(format!("can't save during {:?}", state));
+        }
+        let Some(maze) = world.get_resource::<Maze>() else {
+            return Err("no maze loaded".to_string());
+        };
+        let maze = maze.clone();
+
+        let (pacman, ghosts) = capture_actors(world)?;
+
+        let mut fruit_query = world.query::<&Fruit>();
+        let fruit = fruit_query.iter(world).next().cloned();
+
+        let mut pellets: Vec<(usize, usize)> = world.resource::<PelletGroup>().tiles().collect();
+        pellets.sort();
+
+        Ok(SaveGame {
+            maze: maze.to_map_string(),
+            state,
+            state_timer: world.resource::<StateTimer>().0.clone(),
+            stats: world.resource::<GameStats>().clone(),
+            schedule: world.resource::<GhostModeSchedule>().clone(),
+            house: world.resource::<GhostHouse>().clone(),
+            fruit_tracker: world.resource::<FruitTracker>().clone(),
+            players: world.resource::<Players>().clone(),
+            fruit,
+            rng: world.resource::<GameRng>().clone(),
+            pellets,
+            pacman,
+            ghosts,
+        })
+    }
+
+    // Put the world back the way it was saved; nothing is touched if the save doesn't fit
+    pub fn restore(&self, world: &mut World) -> Result<(), String> {
+        let state = *world.resource::<State<GameState>>().get();
+        if !matches!(state, GameState::Ready | GameState::Playing | GameState::Dying) {
+            return Err(format!("can't load during {:?}", state));
+        }
+        let Some(maze) = world.get_resource::<Maze>() else {
+            return Err("no maze loaded".to_string());
+        };
+        if Maze::from_map_str(&self.maze) != *maze {
+            return Err("saved on a different maze".to_string());
+        }
+        let maze = maze.clone();
+        let theme = world.resource::<Theme>().clone();
+
+        // Actors go first, they check every node before changing anything
+        restore_actors(world, &self.pacman, &self.ghosts)?;
+
+        world.insert_resource(StateTimer(self.state_timer.clone()));
+        world.insert_resource(self.stats.clone());
+        world.insert_resource(self.schedule.clone());
+        world.insert_resource(self.house.clone());
+        world.insert_resource(self.fruit_tracker.clone());
+        world.insert_resource(self.players.clone());
+        world.insert_resource(self.rng.clone());
+        if self.state != state {
+            world.resource_mut::<NextState<GameState>>().set(self.state);
+        }
+
+        // Pellets and fruit are swapped for fresh entities
+        world.resource_scope(|world, mut pellet_group: Mut<PelletGroup>| {
+            pellet_group.restore_pellets(&mut world.commands(), &maze, &theme, self.pellets.iter().copied());
+        });
+        restore_fruit(world, self.fruit.as_ref(), &maze, &theme);
+        Ok(())
+    }
+
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, contents)?;
+        fs::rename(&temp_path, path)
+    }
+
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(io::Error::other)
+    }
+}
+
+// Where Pac-Man and the ghosts are, by node cell
+pub fn capture_actors(world: &mut World) -> Result<(SavedPacman, Vec<SavedGhost>), String> {
+    let tile_of = |world: &World, entity: Entity| {
+        world
+            .get::<MapNode>(entity)
+            .map(|node| node.tile())
+            .ok_or_else(|| "actor is not on a node of the maze".to_string())
+    };
+
+    let mut pacman_query = world.query::<(&Pacman, &SkipTicks)>();
+    let Ok((pacman, skip_ticks)) = pacman_query.get_single(world) else {
+        return Err("no Pac-Man to save".to_string());
+    };
+    let pacman = SavedPacman {
+        current_node: tile_of(world, pacman.current_node)?,
+        target_node: pacman.target_node.map(|node| tile_of(world, node)).transpose()?,
+        edge_progress: pacman.edge_progress,
+        edge_length: pacman.edge_length,
+        node_direction: pacman.node_direction,
+        facing: pacman.facing,
+        queued_direction: pacman.queued_direction,
+        skip_ticks: skip_ticks.remaining,
+    };
+
+    let mut ghost_query = world.query::<(&Ghost, &SkipTicks)>();
+    let ghosts = ghost_query
+        .iter(world)
+        .map(|(ghost, skip_ticks)| {
+            Ok(SavedGhost {
+                name: ghost.name,
+                mode: ghost.mode,
+                current_node: tile_of(world, ghost.current_node)?,
+                target_node: ghost.target_node.map(|node| tile_of(world, node)).transpose()?,
+                edge_progress: ghost.edge_progress,
+                edge_length: ghost.edge_length,
+                node_direction: ghost.node_direction,
+                frightened_timer: ghost.frightened_timer.clone(),
+                elroy_stage: ghost.elroy_stage,
+                skip_ticks: skip_ticks.remaining,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok((pacman, ghosts))
+}
+
+// Move Pac-Man and the ghosts back onto their saved edges
+// Every node is looked up first so nothing moves if one is missing
+pub fn restore_actors(world: &mut World, pacman: &SavedPacman, ghosts: &[SavedGhost]) -> Result<(), String> {
+    let node_group = world.resource::<NodeGroup>();
+    let node_at = |tile: (usize, usize)| node_group.node_at(tile).ok_or_else(|| format!("no node at {:?}", tile));
+    let (current_node, target_node) = (node_at(pacman.current_node)?, pacman.target_node.map(node_at).transpose()?);
+    let ghost_nodes = ghosts
+        .iter()
+        .map(|ghost| Ok((ghost.name, (node_at(ghost.current_node)?, ghost.target_node.map(node_at).transpose()?))))
+        .collect::<Result<HashMap<_, _>, String>>()?;
+    let node_position = |world: &World, node: Entity| world.get::<MapNode>(node).map_or(Vec2::ZERO, |node| node.position);
+
+    let current_position = node_position(world, current_node);
+    let mut pacman_query = world.query::<(&mut Pacman, &mut Transform, &mut TileOccupancy, &mut SkipTicks)>();
+    for (mut live, mut transform, mut occupancy, mut skip_ticks) in pacman_query.iter_mut(world) {
+        live.current_node = current_node;
+        live.target_node = target_node;
+        live.node_position = current_position;
+        live.edge_progress = pacman.edge_progress;
+        live.edge_length = pacman.edge_length;
+        live.node_direction = pacman.node_direction;
+        live.facing = pacman.facing;
+        live.queued_direction = pacman.queued_direction;
+        skip_ticks.remaining = pacman.skip_ticks;
+        transform.translation = live.position().extend(transform.translation.z);
+        *occupancy = TileOccupancy::new(live.position());
+    }
+
+    let ghost_positions: HashMap<GhostName, Vec2> = ghost_nodes
+        .iter()
+        .map(|(&name, &(current_node, _))| (name, node_position(world, current_node)))
+        .collect();
+    let mut ghost_query = world.query::<(&mut Ghost, &mut Transform, &mut TileOccupancy, &mut SkipTicks)>();
+    for (mut ghost, mut transform, mut occupancy, mut skip_ticks) in ghost_query.iter_mut(world) {
+        let Some(saved) = ghosts.iter().find(|saved| saved.name == ghost.name) else {
+            continue;
+        };
+        let (current_node, target_node) = ghost_nodes[&ghost.name];
+        ghost.mode = saved.mode;
+        ghost.current_node = current_node;
+        ghost.target_node = target_node;
+        ghost.edge_progress = saved.edge_progress;
+        ghost.edge_length = saved.edge_length;
+        ghost.node_direction = saved.node_direction;
+        ghost.frightened_timer = saved.frightened_timer.clone();
+        ghost.elroy_stage = saved.elroy_stage;
+        skip_ticks.remaining = saved.skip_ticks;
+        let position = ghost_positions[&ghost.name] + ghost.node_direction.to_vec2() * ghost.edge_progress;
+        transform.translation = position.extend(transform.translation.z);
+        *occupancy = TileOccupancy::new(position);
+    }
+    Ok(())
+}
+
+// Swap whatever fruit is on the board for the saved one
+pub fn restore_fruit(world: &mut World, fruit: Option<&Fruit>, maze: &Maze, theme: &Theme) {
+    let fruits: Vec<Entity> = world.query_filtered::<Entity, With<Fruit>>().iter(world).collect();
+    for fruit in fruits {
+        world.despawn(fruit);
+    }
+    if let (Some(fruit), Some(tile)) = (fruit, maze.fruit_tile) {
+        spawn_fruit(&mut world.commands(), theme, fruit.clone(), tile);
+    }
+    world.flush();
+}
+
+// F5: write the game in progress to disk
+pub fn save_game_system(world: &mut World) {
+    let path = SaveGame::default_path();
+    match SaveGame::capture(world) {
+        Ok(save) => match save.save_to(&path) {
+            Ok(()) => info!("Saved game to {}", path.display()),
+            Err(e) => eprintln!("Failed to write save {}: {}", path.display(), e),
+        },
+        Err(e) => eprintln!("Can't save the game: {}", e),
+    }
+}
+
+// F9: pick up where the last save left off
+pub fn load_game_system(world: &mut World) {
+    let path = SaveGame::default_path();
+    let save = match SaveGame::load_from(&path) {
+        Ok(save) => save,
+        Err(e) => {
+            eprintln!("Failed to read save {}: {}", path.display(), e);
+            return;
+        }
+    };
+    match save.restore(world) {
+        Ok(()) => info!("Loaded game from {}", path.display()),
+        Err(e) => eprintln!("Can't load the game: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Constants
+    const STEPS: usize = 300;
+
+    // Turn every so often so Pac-Man covers some of the maze
+    fn play(env: &mut PacmanEnv) -> Vec<(Vec<f32>, f32, bool)> {
+        (0..STEPS)
+            .map(|step| {
+                let action = PacManDirection::ALL[(step / 25) % PacManDirection::ALL.len()];
+                let (observation, reward, done) = env.step(action).unwrap();
+                (observation.to_features(), reward, done)
+            })
+            .collect()
+    }
+
+    // Compared as JSON values, so map fields don't depend on their iteration order
+    fn json(save: &SaveGame) -> serde_json::Value {
+        serde_json::to_value(save).unwrap()
+    }
+
+    #[test]
+    fn restored_game_plays_out_the_same() {
+        let mut env = PacmanEnv::new();
+        env.reset(3).unwrap();
+        play(&mut env);
+        let save = env.save().unwrap();
+
+        // Through a file and back
+        let path = std::env::temp_dir().join(format!("pacman_savegame_{}.json", std::process::id()));
+        save.save_to(&path).unwrap();
+        let loaded = SaveGame::load_from(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(json(&loaded), json(&save));
+
+        let first = play(&mut env);
+        env.restore(&loaded).unwrap();
+        assert_eq!(json(&env.save().unwrap()), json(&save));
+        assert!(play(&mut env) == first);
+    }
+
+    #[test]
+    fn save_from_another_maze_is_refused() {
+        let mut env = PacmanEnv::new();
+        env.reset(3).unwrap();
+        let mut save = env.save().unwrap();
+        save.maze = save.maze.replacen('+', "X", 1);
+        assert!(env.restore(&save).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use crate::{collision, fruit, gameplay, ghost, ghost_house, node, pacman, pellet, players};
+
+// The rules of the game with no window, sound or screens attached
+// Shared by the game itself and the headless environment
+// Expects GameRng, PacmanController and HighScoreTable to be inserted by whoever adds it
+pub struct SimulationPlugin;
+
+// Every system that advances the game, so a network client can leave it to the server
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationSet;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<GameState>()
+            .insert_resource(StateTimer::new(INTRO_SECONDS))
+            .insert_resource(NodeGroup::new())
+            .insert_resource(GhostModeSchedule::new())
+            .insert_resource(GhostHouse::new(1))
+            .insert_resource(GameStats::new())
+            .insert_resource(PelletGroup::new())
+            .insert_resource(FruitTracker::new())
+            .insert_resource(Players::new())
+            .insert_resource(GhostPlayer::new())
+            .add_event::<PacmanDeathEvent>()
+            .add_event::<GhostEatenEvent>()
+            .add_event::<PelletEatenEvent>()
+            .add_event::<FruitEatenEvent>()
+            .add_event::<LevelClearedEvent>()
+            .add_event::<PlayerSwapEvent>()
+            .add_systems(
+                Startup,
+                (
+                    node::load_maze,
+                    node::maze_to_nodes,
+                    node::assign_neighbors,
+                    pellet::spawn_pellets,
+                    pacman::Pacman::spawn_pacman,
+                    ghost::Ghost::spawn_ghosts,
+                ).chain()
+            )
+            .add_systems(Update, (
+                gameplay::pacman_input_system,
+                gameplay::pacman_node_based_movement_system,
+                ghost::ghost_mode_system,
+                ghost::ghost_movement_system,
+            )
+                .run_if(in_state(GameState::Playing))
+                .in_set(SimulationSet)
+            )
+            // Collisions are checked once everyone has moved
+            .add_systems(Update, (
+                collision::update_tile_occupancy_system,
+                collision::pacman_ghost_collision_system,
+                ghost::ghost_eaten_system,
+                gameplay::pacman_death_system,
+                pellet::pellet_eating_system,
+                gameplay::eating_stall_system,
+                ghost::cruise_elroy_system,
+                ghost::power_pellet_system,
+                fruit::fruit_spawn_system,
+                fruit::fruit_eating_system,
+                gameplay::level_clear_system,
+                ghost_house::ghost_release_system,
+            )
+                .chain()
+                .after(gameplay::pacman_node_based_movement_system)
+                .after(ghost::ghost_movement_system)
+                .run_if(in_state(GameState::Playing))
+                .in_set(SimulationSet)
+            )
+            .add_systems(Update, fruit::fruit_timer_system.run_if(in_state(GameState::Playing)).in_set(SimulationSet))
+            .add_systems(Update, gameplay::ready_state_system.run_if(in_state(GameState::Ready)).in_set(SimulationSet))
+            .add_systems(Update, gameplay::dying_state_system.run_if(in_state(GameState::Dying)).in_set(SimulationSet))
+            .add_systems(Update, players::player_swap_system.after(gameplay::dying_state_system).in_set(SimulationSet));
+    }
+}
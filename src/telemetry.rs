@@ -0,0 +1,167 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use serde::Serialize;
+use std::io::{BufWriter, Write};
+use std::net::TcpStream;
+
+// Streams what happens in a game as newline-delimited JSON for dashboards and run analysis
+//   PACMAN_TELEMETRY=run.ndjson              appends to a file
+//   PACMAN_TELEMETRY=tcp://127.0.0.1:7778    writes to whatever is listening there
+// One object per line, e.g. {"frame":812,"time":13.5,"event":"pellet_eaten","kind":"Pellet","tile":[6,8]}
+pub struct TelemetryPlugin;
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(telemetry) = Telemetry::from_env() else {
+            return;
+        };
+        // After Update so every gameplay event of the frame has been sent
+        app.insert_resource(telemetry)
+            .add_systems(PostUpdate, telemetry_system);
+    }
+}
+
+// Constants
+const TELEMETRY_VARIABLE: &str = "PACMAN_TELEMETRY";
+const TCP_PREFIX: &str = "tcp://";
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TelemetryEvent {
+    PelletEaten { kind: PelletKind, tile: (usize, usize) },
+    GhostEaten { ghost: GhostName },
+    FruitEaten { kind: FruitKind, points: u32 },
+    Death { ghost: Option<GhostName>, lives: u32 },
+    LevelCleared { level: u32 },
+    ModeChange { ghost: GhostName, from: GhostMode, to: GhostMode },
+    ScoreChange { from: u32, to: u32 },
+}
+
+#[derive(Serialize)]
+struct TelemetryLine<'a> {
+    frame: u64,
+    time: f32,
+    #[serde(flatten)]
+    event: &'a TelemetryEvent,
+}
+
+// Where the lines go; dropped after the first failed write so the game carries on
+#[derive(Resource)]
+pub struct Telemetry {
+    sink: Option<Box<dyn Write + Send + Sync>>,
+    frame: u64,
+}
+
+impl Telemetry {
+    pub fn from_env() -> Option<Self> {
+        let target = std::env::var(TELEMETRY_VARIABLE).ok()?;
+        match Self::open(&target) {
+            Ok(telemetry) => {
+                info!("Streaming telemetry to {}", target);
+                Some(telemetry)
+            }
+            Err(e) => {
+                eprintln!("Failed to open telemetry sink {}: {}", target, e);
+                None
+            }
+        }
+    }
+
+    pub fn open(target: &str) -> io::Result<Self> {
+        let sink: Box<dyn Write + Send + Sync> = match target.strip_prefix(TCP_PREFIX) {
+            Some(address) => Box::new(TcpStream::connect(address)?),
+            None => {
+                let file = std::fs::OpenOptions::new().create(true).append(true).open(target)?;
+                Box::new(BufWriter::new(file))
+            }
+        };
+        Ok(Telemetry {
+            sink: Some(sink),
+            frame: 0,
+        })
+    }
+
+    pub fn emit(&mut self, time: f32, event: &TelemetryEvent) {
+        let Some(sink) = self.sink.as_mut() else {
+            return;
+        };
+        let line = TelemetryLine {
+            frame: self.frame,
+            time,
+            event,
+        };
+        let result = serde_json::to_writer(&mut *sink, &line)
+            .map_err(io::Error::other)
+            .and_then(|()| sink.write_all(b"\n"));
+        if let Err(e) = result {
+            eprintln!("Telemetry stopped: {}", e);
+            self.sink = None;
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(Err(e)) = self.sink.as_mut().map(|sink| sink.flush()) {
+            eprintln!("Telemetry stopped: {}", e);
+            self.sink = None;
+        }
+    }
+}
+
+// Turn this frame's gameplay events into telemetry lines
+// Mode and score changes have no event of their own, so they are spotted by comparing with last frame
+#[allow(clippy::too_many_arguments)]
+pub fn telemetry_system(
+    mut telemetry: ResMut<Telemetry>,
+    mut pellet_events: EventReader<PelletEatenEvent>,
+    mut ghost_events: EventReader<GhostEatenEvent>,
+    mut fruit_events: EventReader<FruitEatenEvent>,
+    mut death_events: EventReader<PacmanDeathEvent>,
+    mut cleared_events: EventReader<LevelClearedEvent>,
+    mut last_modes: Local<HashMap<GhostName, GhostMode>>,
+    mut last_score: Local<Option<u32>>,
+    time: Res<Time>,
+    stats: Res<GameStats>,
+    ghost_query: Query<&Ghost>,
+) {
+    let now = time.elapsed_seconds();
+    let mut events = Vec::new();
+
+    events.extend(pellet_events.read().map(|event| TelemetryEvent::PelletEaten {
+        kind: event.kind,
+        tile: event.tile,
+    }));
+    events.extend(ghost_events.read().map(|event| TelemetryEvent::GhostEaten { ghost: event.name }));
+    events.extend(fruit_events.read().map(|event| TelemetryEvent::FruitEaten {
+        kind: event.kind,
+        points: event.points,
+    }));
+    events.extend(death_events.read().map(|event| TelemetryEvent::Death {
+        ghost: ghost_query.get(event.ghost).ok().map(|ghost| ghost.name),
+        lives: stats.lives,
+    }));
+    events.extend(cleared_events.read().map(|event| TelemetryEvent::LevelCleared { level: event.level }));
+
+    for ghost in ghost_query.iter() {
+        match last_modes.insert(ghost.name, ghost.mode) {
+            Some(from) if from != ghost.mode => events.push(TelemetryEvent::ModeChange {
+                ghost: ghost.name,
+                from,
+                to: ghost.mode,
+            }),
+            _ => {}
+        }
+    }
+
+    match last_score.replace(stats.score) {
+        Some(from) if from != stats.score => events.push(TelemetryEvent::ScoreChange { from, to: stats.score }),
+        _ => {}
+    }
+
+    for event in events.iter() {
+        telemetry.emit(now, event);
+    }
+    if !events.is_empty() {
+        telemetry.flush();
+    }
+    telemetry.frame += 1;
+}
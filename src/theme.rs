@@ -0,0 +1,327 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::path::PathBuf;
+
+// Colors for a maze, read from assets/themes/<name>.json
+// A maze uses the theme named after its file (maze_test.txt -> maze_test.json), otherwise "default";
+// generated mazes take turns with the Ms. Pac-Man palettes by seed
+// PACMAN_THEME=<name or path> picks one for every maze
+// Colors are "#rrggbb" or "#rrggbbaa"; anything left out keeps the arcade color
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    #[serde(with = "hex_color")]
+    pub background: Color,
+    #[serde(with = "hex_color")]
+    pub wall: Color,
+    #[serde(with = "hex_color")]
+    pub grid: Color, // Outline around each floor tile
+    #[serde(with = "hex_color")]
+    pub path: Color, // Corridors between nodes
+    #[serde(with = "hex_color")]
+    pub node: Color,
+    #[serde(with = "hex_color")]
+    pub door: Color,
+    #[serde(with = "hex_color")]
+    pub tunnel: Color,
+    #[serde(with = "hex_color")]
+    pub pellet: Color,
+    #[serde(with = "hex_color")]
+    pub power_pellet: Color,
+    #[serde(with = "hex_color")]
+    pub pacman: Color,
+    pub ghosts: GhostColors,
+    pub fruit: FruitColors,
+    pub hud: HudColors,
+    pub editor: EditorColors,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GhostColors {
+    #[serde(with = "hex_color")]
+    pub blinky: Color,
+    #[serde(with = "hex_color")]
+    pub pinky: Color,
+    #[serde(with = "hex_color")]
+    pub inky: Color,
+    #[serde(with = "hex_color")]
+    pub clyde: Color,
+    #[serde(with = "hex_color")]
+    pub frightened: Color,
+    #[serde(with = "hex_color")]
+    pub eyes: Color,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HudColors {
+    #[serde(with = "hex_color")]
+    pub text: Color,
+    #[serde(with = "hex_color")]
+    pub highlight: Color, // Whose turn it is, initials being entered
+    #[serde(with = "hex_color")]
+    pub screen: Color, // Behind the title, name entry and game over screens
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FruitColors {
+    #[serde(with = "hex_color")]
+    pub cherry: Color,
+    #[serde(with = "hex_color")]
+    pub strawberry: Color,
+    #[serde(with = "hex_color")]
+    pub peach: Color,
+    #[serde(with = "hex_color")]
+    pub apple: Color,
+    #[serde(with = "hex_color")]
+    pub melon: Color,
+    #[serde(with = "hex_color")]
+    pub galaxian: Color,
+    #[serde(with = "hex_color")]
+    pub bell: Color,
+    #[serde(with = "hex_color")]
+    pub key: Color,
+}
+
+// The maze editor's tiles and graph overlay; doors use the maze's door color
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditorColors {
+    #[serde(with = "hex_color")]
+    pub wall: Color,
+    #[serde(with = "hex_color")]
+    pub path: Color,
+    #[serde(with = "hex_color")]
+    pub node: Color,
+    #[serde(with = "hex_color")]
+    pub home: Color,
+    #[serde(with = "hex_color")]
+    pub tunnel: Color,
+    #[serde(with = "hex_color")]
+    pub link: Color, // Lines between linked nodes
+    #[serde(with = "hex_color")]
+    pub disconnected: Color, // Nodes cut off from the main graph
+}
+
+// Constants
+const THEME_DIRECTORY: &str = "assets/themes";
+const DEFAULT_THEME: &str = "default";
+const THEME_VARIABLE: &str = "PACMAN_THEME";
+const GENERATED_THEMES: [&str; 3] = ["ms_pink", "ms_blue", "ms_orange"];
+const GENERATED_PREFIX: &str = "generated_"; // See MazeSource::save_path
+
+impl Default for Theme {
+    // The arcade palette
+    fn default() -> Self {
+        Theme {
+            background: Color::BLACK,
+            wall: Color::srgb(0.0, 0.0, 1.0),
+            grid: Color::srgb(1.0, 1.0, 0.0),
+            path: Color::WHITE,
+            node: Color::srgb(1.0, 0.0, 0.0),
+            door: Color::srgb(1.0, 0.72, 1.0),
+            tunnel: Color::srgb(0.5, 0.3, 1.0),
+            pellet: Color::WHITE,
+            power_pellet: Color::WHITE,
+            pacman: Color::srgb(1.0, 1.0, 0.0),
+            ghosts: GhostColors::default(),
+            fruit: FruitColors::default(),
+            hud: HudColors::default(),
+            editor: EditorColors::default(),
+        }
+    }
+}
+
+impl Default for GhostColors {
+    fn default() -> Self {
+        GhostColors {
+            blinky: Color::srgb(1.0, 0.0, 0.0),
+            pinky: Color::srgb(1.0, 0.72, 1.0),
+            inky: Color::srgb(0.0, 1.0, 1.0),
+            clyde: Color::srgb(1.0, 0.72, 0.32),
+            frightened: Color::srgb(0.13, 0.13, 1.0),
+            eyes: Color::WHITE,
+        }
+    }
+}
+
+impl Default for HudColors {
+    fn default() -> Self {
+        HudColors {
+            text: Color::WHITE,
+            highlight: Color::srgb(1.0, 1.0, 0.0),
+            screen: Color::srgba(0.0, 0.0, 0.0, 0.85),
+        }
+    }
+}
+
+impl Default for FruitColors {
+    fn default() -> Self {
+        FruitColors {
+            cherry: Color::srgb(0.87, 0.0, 0.0),
+            strawberry: Color::srgb(1.0, 0.3, 0.4),
+            peach: Color::srgb(1.0, 0.72, 0.32),
+            apple: Color::srgb(0.75, 0.0, 0.0),
+            melon: Color::srgb(0.0, 0.8, 0.2),
+            galaxian: Color::srgb(1.0, 1.0, 0.0),
+            bell: Color::srgb(1.0, 0.85, 0.0),
+            key: Color::srgb(0.0, 0.9, 1.0),
+        }
+    }
+}
+
+impl Default for EditorColors {
+    fn default() -> Self {
+        EditorColors {
+            wall: Color::srgb(0.0, 0.0, 0.6),
+            path: Color::BLACK,
+            node: Color::srgb(0.15, 0.15, 0.15),
+            home: Color::srgb(0.4, 0.0, 0.4),
+            tunnel: Color::srgb(0.2, 0.1, 0.3),
+            link: Color::WHITE,
+            disconnected: Color::srgb(1.0, 0.5, 0.0),
+        }
+    }
+}
+
+impl Theme {
+    // A name from the themes directory, or a path to a theme file
+    pub fn load(name: &str) -> io::Result<Self> {
+        let path = if name.ends_with(".json") {
+            PathBuf::from(name)
+        } else {
+            Path::new(THEME_DIRECTORY).join(format!("{}.json", name))
+        };
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(io::Error::other)
+    }
+
+    // The theme for where a maze came from
+    pub fn for_maze(source: Option<&MazeSource>) -> Self {
+        if let Ok(name) = std::env::var(THEME_VARIABLE) {
+            match Self::load(&name) {
+                Ok(theme) => return theme,
+                Err(e) => eprintln!("Failed to load theme {}: {}", name, e),
+            }
+        }
+        let maze_theme = match source {
+            Some(MazeSource::File { path, .. }) => Path::new(path)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| Self::load(&Self::theme_name(stem)).ok()),
+            Some(MazeSource::Generated { seed }) => Self::load(Self::generated_theme(*seed)).ok(),
+            None => None,
+        };
+        maze_theme.or_else(|| Self::load(DEFAULT_THEME).ok()).unwrap_or_default()
+    }
+
+    // A saved generated maze keeps the colors it was played with
+    fn theme_name(stem: &str) -> String {
+        let seed = stem.strip_prefix(GENERATED_PREFIX).and_then(|seed| seed.parse().ok());
+        match seed {
+            Some(seed) => Self::generated_theme(seed).to_string(),
+            None => stem.to_string(),
+        }
+    }
+
+    fn generated_theme(seed: u64) -> &'static str {
+        GENERATED_THEMES[(seed % GENERATED_THEMES.len() as u64) as usize]
+    }
+
+    pub fn ghost(&self, name: GhostName) -> Color {
+        match name {
+            GhostName::Blinky => self.ghosts.blinky,
+            GhostName::Pinky => self.ghosts.pinky,
+            GhostName::Inky => self.ghosts.inky,
+            GhostName::Clyde => self.ghosts.clyde,
+        }
+    }
+
+    pub fn pellet(&self, kind: PelletKind) -> Color {
+        match kind {
+            PelletKind::Pellet => self.pellet,
+            PelletKind::PowerPellet => self.power_pellet,
+        }
+    }
+
+    pub fn fruit(&self, kind: FruitKind) -> Color {
+        match kind {
+            FruitKind::Cherry => self.fruit.cherry,
+            FruitKind::Strawberry => self.fruit.strawberry,
+            FruitKind::Peach => self.fruit.peach,
+            FruitKind::Apple => self.fruit.apple,
+            FruitKind::Melon => self.fruit.melon,
+            FruitKind::Galaxian => self.fruit.galaxian,
+            FruitKind::Bell => self.fruit.bell,
+            FruitKind::Key => self.fruit.key,
+        }
+    }
+
+    // How the editor shows a maze cell
+    pub fn editor_cell(&self, cell: MazeCell) -> Color {
+        match cell {
+            MazeCell::Empty => self.editor.wall,
+            MazeCell::Path => self.editor.path,
+            MazeCell::Node => self.editor.node,
+            MazeCell::Door => self.door,
+            MazeCell::Home => self.editor.home,
+            MazeCell::Tunnel => self.editor.tunnel,
+        }
+    }
+}
+
+// Pellets spawn in the theme's colors; this follows a theme swapped in later, and the background
+pub fn apply_theme_system(
+    theme: Res<Theme>,
+    mut clear_color: ResMut<ClearColor>,
+    mut pellet_query: Query<(&Pellet, &mut Sprite)>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+    clear_color.0 = theme.background;
+    for (pellet, mut sprite) in pellet_query.iter_mut() {
+        sprite.color = theme.pellet(pellet.kind);
+    }
+}
+
+// Colors as hex strings in theme files
+mod hex_color {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&color.to_srgba().to_hex())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Srgba::hex(&hex)
+            .map(Color::from)
+            .map_err(|e| serde::de::Error::custom(format!("bad color {:?}: {}", hex, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_mazes_use_the_ms_palettes() {
+        for (seed, name) in GENERATED_THEMES.iter().enumerate() {
+            let expected = Theme::load(name).unwrap();
+            let generated = Theme::for_maze(Some(&MazeSource::Generated { seed: seed as u64 }));
+            assert_eq!((generated.wall, generated.path), (expected.wall, expected.path), "seed {}", seed);
+
+            // Saved from the editor, it keeps its colors
+            let saved = MazeSource::File {
+                path: format!("assets/mazes/generated_{}.txt", seed),
+                format: MazeFormat::Tokens,
+            };
+            let reloaded = Theme::for_maze(Some(&saved));
+            assert_eq!((reloaded.wall, reloaded.path), (expected.wall, expected.path), "seed {}", seed);
+        }
+    }
+}
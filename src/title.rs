@@ -0,0 +1,67 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+
+#[derive(Component)]
+pub struct TitleScreen;
+
+// Ways to play, picked with the number keys on the title screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    OnePlayer,
+    TwoPlayers,  // Taking turns, 1UP then 2UP
+    GhostVersus, // Second player steers Blinky
+}
+
+impl GameMode {
+    const KEYS: [(KeyCode, GameMode); 3] = [
+        (KeyCode::Digit1, GameMode::OnePlayer),
+        (KeyCode::Digit2, GameMode::TwoPlayers),
+        (KeyCode::Digit3, GameMode::GhostVersus),
+    ];
+
+    fn description(&self) -> &'static str {
+        match self {
+            GameMode::OnePlayer => "ONE PLAYER",
+            GameMode::TwoPlayers => "TWO PLAYERS",
+            GameMode::GhostVersus => "GHOST VERSUS",
+        }
+    }
+}
+
+pub fn setup_title(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    let mut lines = vec!["PAC-MAN".to_string(), String::new()];
+    for (index, (_, mode)) in GameMode::KEYS.iter().enumerate() {
+        lines.push(format!("{}  {}", index + 1, mode.description()));
+    }
+    lines.push(String::new());
+    lines.push("GHOST VERSUS: WASD OR GAMEPAD STEERS BLINKY".to_string());
+
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    spawn_screen(&mut commands, TitleScreen, font, &theme, lines);
+}
+
+// Pick a mode and start the game; Enter plays alone
+pub fn title_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    maze: Res<Maze>,
+    mut players: ResMut<Players>,
+    mut ghost_player: ResMut<GhostPlayer>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let mode = if keyboard_input.just_pressed(KeyCode::Enter) {
+        GameMode::OnePlayer
+    } else if let Some(&(_, mode)) = GameMode::KEYS.iter().find(|(key, _)| keyboard_input.just_pressed(*key)) {
+        mode
+    } else {
+        return;
+    };
+    info!("Starting {}", mode.description().to_lowercase());
+
+    let count = if mode == GameMode::TwoPlayers { 2 } else { 1 };
+    players.start(count, &maze);
+    *ghost_player = GhostPlayer::new();
+    if mode == GameMode::GhostVersus {
+        ghost_player.ghost = Some(GhostName::Blinky);
+    }
+    next_state.set(GameState::Ready);
+}
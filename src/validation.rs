@@ -0,0 +1,292 @@
+// Prelude + Other Crates
+use crate::prelude::*;
+use std::collections::VecDeque;
+
+// Structural problems found in a maze
+#[derive(Debug, Default)]
+pub struct MazeReport {
+    pub node_count: usize,
+    pub components: Vec<Vec<(usize, usize)>>, // Node groups linked to each other, largest first
+    pub isolated_nodes: Vec<(usize, usize)>,  // Nodes with no neighbor in any direction
+    pub unreachable_pellets: Vec<(usize, usize)>, // Pellets Pac-Man can't get to
+    pub dangling_paths: Vec<(usize, usize)>, // Path cells not on any edge between two nodes
+    pub asymmetric_links: Vec<((usize, usize), PacManDirection)>, // Built links the neighbor doesn't return
+    pub mismatched_links: Vec<((usize, usize), PacManDirection)>, // Built links that disagree with the grid
+    pub dead_ends: Vec<(usize, usize)>, // Nodes outside the ghost house with a single neighbor
+    pub missing_spawns: Vec<&'static str>,
+}
+
+impl MazeReport {
+    pub fn is_connected(&self) -> bool {
+        self.components.len() <= 1
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "nodes {} | components {} | isolated {} | dead ends {} | unreachable pellets {} | dangling paths {}",
+            self.node_count,
+            self.components.len(),
+            self.isolated_nodes.len(),
+            self.dead_ends.len(),
+            self.unreachable_pellets.len(),
+            self.dangling_paths.len()
+        );
+        if !self.missing_spawns.is_empty() {
+            summary += &format!(" | missing {}", self.missing_spawns.join(", "));
+        }
+        summary
+    }
+
+    // One line per problem, empty if the maze is playable
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for component in self.components.iter().skip(1) {
+            problems.push(format!("{} node(s) disconnected from the maze, starting at {:?}", component.len(), component[0]));
+        }
+        for cell in self.isolated_nodes.iter() {
+            problems.push(format!("node {:?} has no neighbors", cell));
+        }
+        for cell in self.dead_ends.iter() {
+            problems.push(format!("node {:?} is a dead end", cell));
+        }
+        for cell in self.dangling_paths.iter() {
+            problems.push(format!("path {:?} does not lead to a node", cell));
+        }
+        for (cell, direction) in self.asymmetric_links.iter() {
+            problems.push(format!("node {:?} links {:?} but the neighbor doesn't link back", cell, direction));
+        }
+        for (cell, direction) in self.mismatched_links.iter() {
+            problems.push(format!("node {:?} {:?} link doesn't match the maze", cell, direction));
+        }
+        for cell in self.unreachable_pellets.iter() {
+            problems.push(format!("pellet {:?} is unreachable", cell));
+        }
+        for spawn in self.missing_spawns.iter() {
+            problems.push(format!("missing {}", spawn));
+        }
+        problems
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.problems().is_empty()
+    }
+}
+
+// Cells walked over going from a node to its neighbor in a direction, both ends included
+fn edge_cells(maze: &Maze, start: (usize, usize), direction: PacManDirection) -> Vec<(usize, usize)> {
+    let Some((end, _)) = maze.next_node(start.0, start.1, direction) else {
+        return Vec::new();
+    };
+    let (dx, dy) = direction.grid_offset();
+    let mut cells = vec![start];
+    let (mut x, mut y) = (start.0 as isize, start.1 as isize);
+    while (x as usize, y as usize) != end {
+        // Tunnels wrap from one side of the maze to the other
+        x = (x + dx).rem_euclid(maze.width() as isize);
+        y += dy;
+        cells.push((x as usize, y as usize));
+    }
+    cells
+}
+
+// Links out of each node as the game builds them: direction, neighbor cell and the edge itself
+type NodeGraph = HashMap<(usize, usize), Vec<(PacManDirection, (usize, usize), Edge)>>;
+
+fn build_node_graph(maze: &Maze) -> NodeGraph {
+    let mut world = World::new();
+    world.insert_resource(maze.clone());
+    world.insert_resource(NodeGroup::new());
+    let mut schedule = Schedule::default();
+    schedule.add_systems((maze_to_nodes, assign_neighbors).chain());
+    schedule.run(&mut world);
+
+    let mut nodes = world.query::<(Entity, &MapNode)>();
+    let tiles: HashMap<Entity, (usize, usize)> = nodes.iter(&world).map(|(entity, node)| (entity, node.tile())).collect();
+    nodes
+        .iter(&world)
+        .map(|(_, node)| {
+            let links = PacManDirection::ALL
+                .into_iter()
+                .filter_map(|direction| {
+                    let edge = node.edge(direction)?;
+                    Some((direction, *tiles.get(&edge.to)?, edge.clone()))
+                })
+                .collect();
+            (node.tile(), links)
+        })
+        .collect()
+}
+
+// Check connectivity of the node graph, its links and spawns, and which pellets Pac-Man can reach
+pub fn validate_maze(maze: &Maze) -> MazeReport {
+    let nodes = maze.node_cells();
+    let mut report = MazeReport {
+        node_count: nodes.len(),
+        ..default()
+    };
+
+    // Components over the full graph, ghost door included
+    let mut seen: HashSet<(usize, usize)> = HashSet::new();
+    for &start in nodes.iter() {
+        if !seen.insert(start) {
+            continue;
+        }
+        let mut component = vec![start];
+        let mut queue = VecDeque::from([start]);
+        while let Some((x, y)) = queue.pop_front() {
+            for direction in PacManDirection::ALL {
+                if let Some((next, _)) = maze.next_node(x, y, direction) {
+                    if seen.insert(next) {
+                        component.push(next);
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        report.components.push(component);
+    }
+    report.components.sort_by_key(|component| std::cmp::Reverse(component.len()));
+
+    report.isolated_nodes = nodes
+        .iter()
+        .copied()
+        .filter(|&(x, y)| PacManDirection::ALL.into_iter().all(|direction| maze.next_node(x, y, direction).is_none()))
+        .collect();
+
+    // The graph the game builds must match the grid, and every link in it must be walkable both ways
+    let graph = build_node_graph(maze);
+    let house = maze.house_cells();
+    for &(x, y) in nodes.iter() {
+        let links = graph.get(&(x, y)).map(Vec::as_slice).unwrap_or_default();
+        for direction in PacManDirection::ALL {
+            let built = links.iter().find(|(link_direction, _, _)| *link_direction == direction);
+            let matches = match (maze.trace_edge(x, y, direction), built) {
+                (None, None) => true,
+                (Some(expected), Some((_, to, edge))) => {
+                    *to == expected.end
+                        && edge.length == expected.length
+                        && edge.tunnel == expected.tunnel
+                        && edge.wraps == expected.wraps
+                        && edge.one_way == expected.door
+                }
+                _ => false,
+            };
+            if !matches {
+                report.mismatched_links.push(((x, y), direction));
+            }
+            let Some((_, to, _)) = built else {
+                continue;
+            };
+            let links_back = graph
+                .get(to)
+                .is_some_and(|back| back.iter().any(|(back_direction, back_to, _)| *back_direction == direction.opposite() && *back_to == (x, y)));
+            if !links_back {
+                report.asymmetric_links.push(((x, y), direction));
+            }
+        }
+        // Ghosts bounce about inside the house, everywhere else a node needs a way on
+        if links.len() == 1 && !house.contains(&(x, y)) {
+            report.dead_ends.push((x, y));
+        }
+    }
+
+    // Corridors must run from node to node; anything left over is a dead end or a corner without a node
+    let mut edge_covered: HashSet<(usize, usize)> = HashSet::new();
+    for &start in nodes.iter() {
+        for direction in PacManDirection::ALL {
+            edge_covered.extend(edge_cells(maze, start, direction));
+        }
+    }
+    for y in 0..maze.height() {
+        for x in 0..maze.width() {
+            // Door cells may be wider than the corridor through them
+            let cell = maze.cell(x, y);
+            if matches!(cell, MazeCell::Path | MazeCell::Tunnel) && !edge_covered.contains(&(x, y)) {
+                report.dangling_paths.push((x, y));
+            }
+        }
+    }
+
+    // Pac-Man starts on his spawn (or in the largest component) and never goes through the door
+    let mut reachable_cells: HashSet<(usize, usize)> = HashSet::new();
+    let pacman_start = maze
+        .pacman_spawn
+        .or_else(|| report.components.first().and_then(|component| component.first().copied()));
+    if let Some(start) = pacman_start {
+        let mut visited = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some((x, y)) = queue.pop_front() {
+            reachable_cells.insert((x, y));
+            for direction in PacManDirection::ALL {
+                let Some((next, through_door)) = maze.next_node(x, y, direction) else {
+                    continue;
+                };
+                if through_door {
+                    continue;
+                }
+                reachable_cells.extend(edge_cells(maze, (x, y), direction));
+                if visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    report.unreachable_pellets = maze
+        .pellets
+        .keys()
+        .copied()
+        .filter(|cell| !reachable_cells.contains(cell))
+        .collect();
+    report.unreachable_pellets.sort();
+
+    if maze.pacman_spawn.is_none() {
+        report.missing_spawns.push("Pac-Man spawn");
+    }
+    let home = nodes.iter().copied().find(|&(x, y)| maze.cell(x, y) == MazeCell::Home);
+    match home {
+        None => report.missing_spawns.push("ghost home"),
+        // The ghosts leave the house through the door
+        Some((x, y)) => {
+            let has_exit = PacManDirection::ALL
+                .into_iter()
+                .any(|direction| matches!(maze.next_node(x, y, direction), Some((_, true))));
+            if !has_exit {
+                report.missing_spawns.push("ghost house exit");
+            }
+        }
+    }
+    if maze.fruit_tile.is_none() {
+        report.missing_spawns.push("fruit spawn");
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_maze_is_valid() {
+        let maze = Maze::read_map(DEFAULT_MAZE_PATH).unwrap();
+        let report = validate_maze(&maze);
+        assert!(report.is_valid(), "{:?}", report.problems());
+    }
+
+    #[test]
+    fn reports_dead_ends() {
+        let maze = Maze::from_map_str("X X X X X\nX n - n X\nX X X - X\nX X X n X\nX X X X X");
+        let mut dead_ends = validate_maze(&maze).dead_ends;
+        dead_ends.sort();
+        assert_eq!(dead_ends, vec![(1, 1), (3, 3)]);
+    }
+
+    #[test]
+    fn tunnel_wrap_is_not_a_dead_end() {
+        let maze = Maze::from_map_str("X X X X X\nn T n T n\nX X X X X");
+        let report = validate_maze(&maze);
+        assert!(report.dead_ends.is_empty());
+        assert!(report.asymmetric_links.is_empty());
+        assert!(report.mismatched_links.is_empty());
+    }
+}